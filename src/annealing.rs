@@ -0,0 +1,595 @@
+use crate::budget::Budget;
+use crate::dfs::*;
+use crate::engine::{DfsLimit, PathState};
+use crate::freespace::FreeSpaceMap;
+use crate::region::region_initial_state;
+use crate::safety::SafetyNet;
+use crate::segment::optimize_segment;
+use crate::tail::{extend_tail_with, TailPolicy};
+use crate::topk::{edge_distance, TopK};
+use crate::*;
+use std::collections::BinaryHeap;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+// 繋ぎ直しのDFSで残しておく経路の数 (上から順に確率0.25で飛ばすので4個あれば足りる)
+const REROUTE_CANDIDATE_SIZE: usize = 4;
+// 空きの多いところとしてfree_spaceの上位FREE_SPACE_POOL_SIZE個からFREE_SPACE_CANDIDATE_SIZE個取ってくる
+const FREE_SPACE_POOL_SIZE: usize = 32;
+const FREE_SPACE_CANDIDATE_SIZE: usize = 6;
+
+/// 焼きなましのパラメータ
+/// time_limit: 全体の制限時間(秒)．budget::Budgetで初期解のDFS・初期解ごとの焼きなまし・仕上げに配り，これを過ぎる前に返す
+/// max_iterations: 焼きなましの反復回数の上限 (全初期解の合計．Someなら温度も反復回数で進める)
+///   時間に依存せず結果を再現したいとき(テストなど)はこちらとDfsLimit::nodesを使う
+/// solution_size: 焼きなます初期解の数 (焼きなましの時間を初期解ごとに等分し，早く終わった分は後ろに回す)
+/// t0, t1: 焼きなましの初期温度と終温度
+/// seed: 乱数のシード
+/// dfs_orders: 初期解構築のDFSで子を見る順番 (順番ごとにDFSを1回ずつする．デフォルトはDIR_LISTの8通り)
+/// first_dfs_limit: 初期解構築のDFSの打ち切り条件(dfs_ordersの1つあたり．時間で打ち切るときはBudgetの初期解の段階を過ぎないように縮める)
+/// initial_min_distance: 0より大きければ，初期解同士が片方にしかない辺をこの数以上もつようにする (topk::edge_distance)
+///   DFSの順番が違っても似た経路ばかりになり，焼きなましが同じところばかり探すのを防ぐ．このとき各順番のDFSからもsolution_size個ずつ候補を取る
+/// reroute_limit: p1->p2の繋ぎ直し1回の打ち切り条件
+/// region_block_size: Someなら盤面をこの大きさのブロックに分けて作る経路(region::region_initial_state)も初期解に加える
+/// region_dfs_limit: そのときのブロック内のDFSの打ち切り条件(ブロック1つ・方向の順1つあたり)
+/// initial_output: 以前の実行で得た経路(LRUD)．あれば初期解の1つに加える (不正な経路なら使わない)
+/// tail_policy: お尻を伸ばすときの手の選び方, tail_rollouts: お尻を何通り伸ばしてみて一番よいものを採るか
+/// branch_and_bound: 繋ぎ直しをランダムDFSではなく分枝限定法(segment::optimize_segment)でやるか
+/// prefix_rebuild_prob: 1反復でp1, p2を選ぶ代わりに，sから最初のn手(nは1~prefix_rebuild_len)をDFSで作り直してn手目に繋ぐ確率
+/// tail_regrow_prob: 1反復でp1, p2を選ぶ代わりに，経路をどこかで切ってtail_regrow_policyでお尻を伸ばし直す確率
+///   どちらも得点の差分は作り直した部分だけから計算し，繋ぎ変えと同じ基準で遷移する
+/// polish_fraction: 最後に最良解を一番低い温度で焼いて仕上げる時間の割合 (max_iterationsがSomeなら反復回数の割合)
+/// safety_net: Someなら最良解が更新されるたびに渡しておく (途中でpanicしてもそれまでの最良解を出力できるように)
+/// free_space_map: 「近くに空きがたくさんあるところを見る」近傍で，経路の空き具合を毎回数え直さずにfreespace::FreeSpaceMapで差分更新して持つか
+#[derive(Clone, Debug)]
+pub struct AnnealingConfig {
+    pub time_limit: f64,
+    pub max_iterations: Option<usize>,
+    pub solution_size: usize,
+    pub t0: f64,
+    pub t1: f64,
+    pub seed: u128,
+    pub dfs_orders: Vec<DfsOrder>,
+    pub first_dfs_limit: DfsLimit,
+    pub initial_min_distance: usize,
+    pub reroute_limit: DfsLimit,
+    pub region_block_size: Option<usize>,
+    pub region_dfs_limit: DfsLimit,
+    pub initial_output: Option<Output>,
+    pub tail_policy: TailPolicy,
+    pub tail_rollouts: usize,
+    pub branch_and_bound: bool,
+    pub prefix_rebuild_prob: f64,
+    pub prefix_rebuild_len: usize,
+    pub tail_regrow_prob: f64,
+    pub tail_regrow_policy: TailPolicy,
+    pub polish_fraction: f64,
+    pub free_space_map: bool,
+    pub safety_net: Option<SafetyNet>,
+}
+
+impl Default for AnnealingConfig {
+    // 最終提出(src/bin/0.rs)の値
+    // この問題は解の改善幅が10^3オーダーくらい
+    // 仮に1000悪くなりT=1000のとき，e^(-1) = 1/2.7くらいの確率で採用される
+    fn default() -> Self {
+        Self {
+            time_limit: 1.993,
+            max_iterations: None,
+            solution_size: 1,
+            t0: 10000.,
+            t1: 20.,
+            seed: 20221210,
+            dfs_orders: fixed_dfs_orders(),
+            first_dfs_limit: DfsLimit::time(0.004),
+            initial_min_distance: 0,
+            reroute_limit: DfsLimit::time(0.0015),
+            region_block_size: None,
+            region_dfs_limit: DfsLimit::nodes(300),
+            initial_output: None,
+            tail_policy: TailPolicy::Random,
+            tail_rollouts: 1,
+            branch_and_bound: false,
+            prefix_rebuild_prob: 0.0,
+            prefix_rebuild_len: 30,
+            tail_regrow_prob: 0.0,
+            tail_regrow_policy: TailPolicy::Warnsdorff,
+            polish_fraction: 0.0,
+            free_space_map: false,
+            safety_net: None,
+        }
+    }
+}
+
+/// DFSで初期解をいくつか作り，それぞれ経路の繋ぎ変えを近傍とする焼きなましで改善する
+/// 時間は初期解のDFS・初期解ごとの焼きなまし・最後の仕上げの順にBudgetで配る
+/// (最良スコア, 最良の出力) を返す
+pub fn solve(input: &Input, config: &AnnealingConfig) -> (i32, Output) {
+    let mut budget = Budget::new(config.time_limit, &phase_plan(config));
+    let mut rng = Pcg64Mcg::new(config.seed);
+
+    // =========== [part1] sを始点とする初期解をいくつかDFSで構築する ===========
+    let init_end = budget.begin("init");
+    let mut state_bh = make_initial_states_until(&mut rng, input, config, init_end);
+
+    // =========== [part2] すでに訪問した頂点から2点p1,p2を選び，テキトーに繋ぎ変える ===========
+    let mut best_output = String::new();
+    let mut best_score = 0;
+    if let Some(state) = state_bh.peek() {
+        best_score = state.game_score_;
+        best_output = state.output_.clone();
+    }
+    // safety_netに最後に渡した得点
+    let mut offered_score = i32::MIN;
+    let mut offer_best = |best_score: i32, best_output: &Output| {
+        if let Some(net) = &config.safety_net {
+            if best_score > offered_score {
+                offered_score = best_score;
+                net.offer(best_output);
+            }
+        }
+    };
+    offer_best(best_score, &best_output);
+    // 各初期解に配る反復回数
+    let phase_iterations = config.max_iterations.map(|max_iterations| max_iterations / config.solution_size.max(1));
+    let mut state_iter = 0;
+    while !budget.over() && state_iter < config.solution_size {
+        // 各初期解それぞれに対して焼きなましで改善するかを調べる
+        state_iter += 1;
+        let mut crt_state = match state_bh.pop() {
+            Some(state) => state,
+            None => break,
+        };
+        // 各初期解の持ち時間は残り時間の等分 (前の初期解が早く終わればその分長くなる)
+        budget.begin("anneal");
+        let mut free_space = config.free_space_map.then(|| FreeSpaceMap::new(input, &crt_state));
+        let mut iter = 0;
+        while !budget.phase_over() && phase_iterations.is_none_or(|n| iter < n) {
+            iter += 1;
+            let t = schedule_progress(&budget, phase_iterations, state_iter, iter);
+            let cont = anneal_step_with(&mut rng, input, config, &mut crt_state, t, &mut best_score, &mut best_output, free_space.as_mut());
+            offer_best(best_score, &best_output);
+            if !cont {
+                break;
+            }
+        }
+        // 焼いている途中で最後にお尻を伸ばした分もbestに反映しておく
+        if crt_state.game_score_ > best_score {
+            best_score = crt_state.game_score_;
+            best_output = crt_state.output_.clone();
+        }
+        offer_best(best_score, &best_output);
+    }
+
+    // =========== [part3] 最良解を一番低い温度で焼いて仕上げる ===========
+    if config.polish_fraction > 0.0 && !best_output.is_empty() {
+        budget.begin("polish");
+        let polish_iterations = config.max_iterations.map(|max_iterations| (max_iterations as f64 * config.polish_fraction) as usize);
+        let mut crt_state = State::fromOutput(input, &best_output).unwrap();
+        let mut iter = 0;
+        while !budget.phase_over() && polish_iterations.is_none_or(|n| iter < n) {
+            iter += 1;
+            let cont = anneal_step(&mut rng, input, config, &mut crt_state, 1.0, &mut best_score, &mut best_output);
+            offer_best(best_score, &best_output);
+            if !cont {
+                break;
+            }
+        }
+    }
+    (best_score, best_output)
+}
+
+// 焼きなましの進み具合t (0~1)
+// 温度は初期解ごとにやり直さず，始めてからstate_iter個目の初期解の締め切りまでの割合で進める
+// (1個目は0から1まで，2個目は1/2くらいから1まで，…．反復回数で止めるときも同じ形にする)
+fn schedule_progress(budget: &Budget, phase_iterations: Option<usize>, state_iter: usize, iter: usize) -> f64 {
+    match phase_iterations {
+        Some(n) => ((state_iter - 1) * n + iter) as f64 / (state_iter * n) as f64,
+        None => budget.elapsed() / (budget.phase_end() - budget.start_time()),
+    }
+}
+
+// solveの時間の配り方
+// 初期解のDFSはfirst_dfs_limitの時間 × DFSの回数，仕上げはpolish_fraction，残りを初期解の数で等分する
+fn phase_plan(config: &AnnealingConfig) -> Vec<(&'static str, f64)> {
+    let init_time = config.first_dfs_limit.time_limit.map_or(0.0, |tl| tl * config.dfs_orders.len() as f64);
+    let init_weight = (init_time / config.time_limit).min(1.0);
+    let anneal_weight = (1.0 - init_weight - config.polish_fraction).max(0.0) / config.solution_size.max(1) as f64;
+    let mut plan = vec![("init", init_weight)];
+    plan.extend((0..config.solution_size).map(|_| ("anneal", anneal_weight)));
+    plan.push(("polish", config.polish_fraction));
+    plan
+}
+
+/// 焼きなましの1反復
+/// お尻を伸ばしてから2点p1, p2を選んで繋ぎ変え，遷移するならcrt_stateを書き換える
+/// 得点がbest_score以上の経路が出てきたらbest_score, best_outputも更新する
+/// t: 焼きなましの進み具合 (0~1)
+/// 経路が短すぎて2点を選べない(これ以上焼けない)ときはfalseを返す
+pub fn anneal_step(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, crt_state: &mut State, t: f64, best_score: &mut i32, best_output: &mut Output) -> bool {
+    anneal_step_with(rng, input, config, crt_state, t, best_score, best_output, None)
+}
+
+/// free_spaceを使う版 (crt_stateに合わせて持っておき，お尻を伸ばしたときと遷移したときに更新する)
+#[allow(clippy::too_many_arguments)]
+pub fn anneal_step_with(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, crt_state: &mut State, t: f64, best_score: &mut i32, best_output: &mut Output, mut free_space: Option<&mut FreeSpaceMap>) -> bool {
+    // お尻を伸ばせるなら伸ばしてもらう
+    let last_idx = crt_state.steps_.len() - 1;
+    extend_tail_with(rng, input, crt_state, config.tail_policy, config.tail_rollouts);
+    crt_state.evaluateScore();
+    if let Some(free_space) = free_space.as_deref_mut() {
+        if crt_state.steps_.len() - 1 > last_idx {
+            free_space.sync(input, crt_state, last_idx);
+        }
+    }
+    // 経路が短すぎて2点を選べない
+    if crt_state.steps_.len() < 2 {
+        return false;
+    }
+    let T = config.t0.powf(1.0 - t) * config.t1.powf(t);
+
+    // 経路の両端を作り直す近傍 (確率が0なら乱数も使わない)
+    if config.prefix_rebuild_prob > 0.0 && rng.gen_bool(config.prefix_rebuild_prob) {
+        let n = rng.gen_range(1, config.prefix_rebuild_len.max(1) + 1).min(crt_state.steps_.len() - 1);
+        if let Some((delta, actions)) = rebuild_prefix(rng, input, config, crt_state, n) {
+            if accepts(rng, delta, T) {
+                *crt_state = splice(input, &crt_state.output_, 0, &actions, n);
+                crt_state.evaluateScore();
+                update_best(crt_state, best_score, best_output);
+                if let Some(free_space) = free_space {
+                    free_space.sync(input, crt_state, 0);
+                }
+            }
+        }
+        return true;
+    }
+    if config.tail_regrow_prob > 0.0 && rng.gen_bool(config.tail_regrow_prob) {
+        let cut_idx = rng.gen_range(0, crt_state.steps_.len() - 1);
+        let next_state = regrow_tail(rng, input, config, crt_state, cut_idx);
+        if accepts(rng, next_state.game_score_ - crt_state.game_score_, T) {
+            *crt_state = next_state;
+            update_best(crt_state, best_score, best_output);
+            if let Some(free_space) = free_space {
+                free_space.sync(input, crt_state, cut_idx);
+            }
+        }
+        return true;
+    }
+
+    // これまでの軌跡から2点を選び，修正する
+    let (p1_idx, p2_idx) = choose_points_with(rng, input, crt_state, t, free_space.as_deref());
+    let steps = &crt_state.steps_;
+    let p1 = steps[p1_idx];
+    let p2 = steps[p2_idx];
+
+    // p1->p2への経路のseenをfalseにする
+    // 注：p1上から始めるのでp1のseenはfalseにしない
+    let mut seen = crt_state.seen_.clone();
+    for p_idx in p1_idx+1..=p2_idx {
+        seen[input.tiles[steps[p_idx].0][steps[p_idx].1]] = false;
+    }
+
+    // p1->p2への経路のひとつを探す
+    let actions = if config.branch_and_bound {
+        // 分枝限定法で(時間内なら)最善の繋ぎ方を求める
+        match optimize_segment(input, &mut seen, p1, p2, config.reroute_limit) {
+            Some(path) => path.actions,
+            None => return true,
+        }
+    } else {
+        let mut actions = vec![];
+        let mut action_top = TopK::new(REROUTE_CANDIDATE_SIZE);
+        let start = PathState::new(input, p1, seen, 0);
+        dfs_to_destination(rng, input, &DIR_LIST, start, p2, &mut action_top, config.reroute_limit);
+        // p1->p2への経路がなければやり直し，あればどれか選ぶ
+        if action_top.is_empty() {return true;}
+        let mut cnt = 0;
+        for actions_tuple in action_top.into_sorted_vec() {
+            actions = actions_tuple.1;
+            // できるだけいいactionを選ぶが，確率的に悪いのも選ぶ (焼く余地ができる)
+            if rng.gen_bool(0.25) && cnt < REROUTE_CANDIDATE_SIZE - 1 {
+                cnt += 1;
+                continue;
+            }
+            else {break;}
+        }
+        actions
+    };
+    let mut next_state = splice(input, &crt_state.output_, p1_idx, &actions, p2_idx);
+    next_state.evaluateScore();
+
+    // 実際のゲームの得点に対し，best_scoreを更新したら常にbest_outputを更新する
+    let next_score = next_state.game_score_;
+    if next_score >= *best_score {
+        *best_score = next_score;
+        *best_output = next_state.output_.clone();
+    }
+
+    // 評価関数の評価値に対し，crt_evaluated_score <= next_evaluated_score か 焼きなましの許容範囲ならnext_stateをcrt_stateとする
+    let crt_evaluated_score = crt_state.evaluated_score_;
+    let next_evaluated_score = next_state.evaluated_score_;
+    if crt_evaluated_score <= next_evaluated_score || rng.gen_bool(((next_evaluated_score - crt_evaluated_score) as f64 / T).exp()) {
+        *crt_state = next_state;
+        if let Some(free_space) = free_space {
+            free_space.sync(input, crt_state, p1_idx);
+        }
+    }
+    true
+}
+
+// 得点がdelta変わる遷移をするか (温度T)
+fn accepts(rng: &mut Pcg64Mcg, delta: i32, T: f64) -> bool {
+    delta >= 0 || rng.gen_bool((delta as f64 / T).exp())
+}
+
+fn update_best(state: &State, best_score: &mut i32, best_output: &mut Output) {
+    if state.game_score_ >= *best_score {
+        *best_score = state.game_score_;
+        *best_output = state.output_.clone();
+    }
+}
+
+/// sから最初のn手を作り直す: steps_[1..=n]を空けて，sからsteps_[n]までランダムな順のDFSで繋ぎ直す
+/// 見つかれば (得点の差分, sからの行動列) を返す．差分は作り直したn手分の得点だけから計算する
+pub fn rebuild_prefix(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, state: &State, n: usize) -> Option<(i32, Actions)> {
+    let steps = &state.steps_;
+    let mut seen = state.seen_.clone();
+    let mut old_score = 0;
+    for &(i, j) in &steps[1..=n] {
+        seen[input.tiles[i][j]] = false;
+        old_score += input.ps[i][j];
+    }
+    let mut action_top = TopK::new(1);
+    let start = PathState::new(input, input.s, seen, 0);
+    dfs_to_destination(rng, input, &DIR_LIST, start, steps[n], &mut action_top, config.reroute_limit);
+    let (new_score, actions) = action_top.into_sorted_vec().into_iter().next()?;
+    Some((new_score - old_score, actions))
+}
+
+/// steps_[cut_idx]より後ろを切り，config.tail_regrow_policyでお尻を伸ばし直した状態
+/// 切るのも伸ばすのも動かした手数分だけで済み，game_score_もその分だけ更新される
+pub fn regrow_tail(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, state: &State, cut_idx: usize) -> State {
+    let mut next_state = state.clone();
+    next_state.retreatTo(input, cut_idx);
+    extend_tail_with(rng, input, &mut next_state, config.tail_regrow_policy, config.tail_rollouts);
+    next_state.evaluateScore();
+    next_state
+}
+
+/// DFSでsを始点とする初期解をいくつか作り，お尻を伸ばし切った状態にして返す
+/// config.dfs_ordersの順それぞれでDFSをして，スコアの高いものからsolution_size個残す
+/// config.initial_min_distance > 0 なら互いに似ていないものだけ残す
+/// config.region_block_sizeやconfig.initial_outputがあればそれらも(お尻を伸ばして)加える
+pub fn make_initial_states(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig) -> BinaryHeap<State> {
+    make_initial_states_until(rng, input, config, f64::INFINITY)
+}
+
+/// DFSをget_time()がdeadlineを過ぎるまでに終える版
+/// first_dfs_limitが時間で打ち切るときは各DFSの時間をdeadlineまでに縮め，過ぎたら残りの順番のDFSはしない (最初の1回は必ずする)
+/// ノード数だけで打ち切るときは結果が変わらないようにdeadlineを見ない
+pub fn make_initial_states_until(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, deadline: f64) -> BinaryHeap<State> {
+    let M = input.tile_count();
+    // 初期解をsolution_size個格納する
+    let diverse = config.initial_min_distance > 0;
+    let mut first_actions = if diverse { TopK::with_diversity(config.solution_size, config.initial_min_distance, edge_distance) } else { TopK::new(config.solution_size) };
+
+    for (k, &order) in config.dfs_orders.iter().enumerate() {
+        let mut limit = config.first_dfs_limit;
+        if let Some(tl) = limit.time_limit {
+            let remaining = deadline - get_time();
+            if k > 0 && remaining <= 0.0 {
+                break;
+            }
+            limit.time_limit = Some(tl.min(remaining.max(0.0)));
+        }
+        // DFSにわたす引数たち
+        let score = input.ps[input.s.0][input.s.1];
+        let start = PathState::new(input, input.s, vec![false; M], score);
+        // DFSの中ではスコアだけ見る (葉ごとに距離を測るとDFSが進まない)．似ていないかはfirst_actionsに入れるときに見る
+        let mut best_actions = TopK::new(if diverse { config.solution_size } else { 1 });
+        dfs_making_first_solution(input, order, start, &mut best_actions, limit);
+        // 一番スコアいいのをもらってくる (1歩も動けない盤面なら空の経路)
+        let candidates = best_actions.into_sorted_vec();
+        if candidates.is_empty() {
+            first_actions.push(score, vec![]);
+        }
+        // 大きい方からsolution_size個残す (diverseなら互いに似ていないものだけ)
+        for (best_score, best_action) in candidates {
+            first_actions.push(best_score, best_action);
+        }
+    }
+
+    // DFSで見つけたactionをstateに施し，初期解とする
+    let mut state_bh = BinaryHeap::new();
+    for score_action in first_actions.into_sorted_vec() {
+        let mut crt_state = State::new(input, !0, input.s);
+        for &action in &score_action.1 {
+            crt_state.advance(input, action);
+        }
+        // DFSで見つけたactionを実行したあと，まだしっぽを伸ばす余地があるならばやりきる
+        extend_tail_with(rng, input, &mut crt_state, config.tail_policy, config.tail_rollouts);
+        crt_state.evaluateScore();
+        state_bh.push(crt_state);
+    }
+
+    // ブロックに分けて回る経路
+    if let Some(block_size) = config.region_block_size {
+        let mut crt_state = region_initial_state(input, block_size, config.region_dfs_limit);
+        extend_tail_with(rng, input, &mut crt_state, config.tail_policy, config.tail_rollouts);
+        crt_state.evaluateScore();
+        state_bh.push(crt_state);
+    }
+
+    // 前回の出力から続きをやる場合
+    // 不正な経路は黙って使わない (知らせたいときは呼ぶ側でState::fromOutputで確かめておく．bin/solve.rsの--initを参照)
+    if let Some(mut crt_state) = config.initial_output.as_ref().and_then(|output| State::fromOutput(input, output).ok()) {
+        extend_tail_with(rng, input, &mut crt_state, config.tail_policy, config.tail_rollouts);
+        crt_state.evaluateScore();
+        state_bh.push(crt_state);
+    }
+
+    state_bh
+}
+
+/// 行ける方向がなくなるまでランダムに進んでお尻を伸ばす
+pub fn extend_tail(rng: &mut Pcg64Mcg, input: &Input, state: &mut State) {
+    extend_tail_with(rng, input, state, TailPolicy::Random, 1);
+}
+
+/// もとのaction = [0...p1....p2......n]として，
+/// 新しいstateをoldaction[0:p1]，newaction[p1:p2], oldaction[p2:n]の順に進めて作る
+/// 注：action[i]でi+1番目のマスに行くことに注意
+/// action[p1_idx-1]でp1_idxのマスに到達している
+pub fn splice(input: &Input, old_output: &str, p1_idx: usize, actions: &[Action], p2_idx: usize) -> State {
+    let mut next_state = State::new(input, !0, input.s);
+    // 最初からp1までは過去のactionで進める
+    next_state.advanceByOutput(input, &old_output[0..p1_idx]);
+    // p1からp2まで新しいactionで進める
+    for &action in actions {
+        next_state.advance(input, action);
+    }
+    // p2の次から最後まで過去のactionで進める
+    next_state.advanceByOutput(input, &old_output[p2_idx..]);
+    next_state
+}
+
+/// 繋ぎ変える2点p1, p2 (steps_のインデックス, p1_idx < p2_idx) を選ぶ
+/// 適当に2点取ってくるパターンと，近くに空きがたくさんあるところを見るパターンがある
+/// t: 焼きなましの進み具合 (0~1)
+pub fn choose_points(rng: &mut Pcg64Mcg, input: &Input, state: &State, t: f64) -> (usize, usize) {
+    choose_points_with(rng, input, state, t, None)
+}
+
+/// free_spaceがあれば，空きがたくさんあるところを経路を全部見ずにfree_spaceの上位から取ってくる版
+pub fn choose_points_with(rng: &mut Pcg64Mcg, input: &Input, state: &State, t: f64, free_space: Option<&FreeSpaceMap>) -> (usize, usize) {
+    let steps = &state.steps_;
+    let seen = &state.seen_;
+    let mut p1_idx;
+    let mut p2_idx;
+    // 時間が進むにつれて探す幅を狭める
+    let width_max = if t < 0.2 {
+        70
+    } else if t < 0.4 {
+        60
+    } else if t < 0.6 {
+        50
+    } else if t < 0.8 {
+        40
+    } else if t < 0.9 {
+        30
+    } else {
+        15
+    };
+    let width_of_search = rng.gen_range(5, width_max);
+    if rng.gen_bool(0.3) {
+        // 適当に2点取ってくるパターン
+        p1_idx = rng.gen_range(0, steps.len()-1);
+        p2_idx = rng.gen_range(p1_idx + 1, std::cmp::min(p1_idx + width_of_search, steps.len()));
+    } else {
+        // 近くに空きがたくさんあるところを見るパターン
+        // 空きマスの数でソートされたbinaryheapに入れていく (多いところを優先して見たいかも)
+        let mut neighbor_empty_p_bh = BinaryHeap::new();
+        if let Some(free_space) = free_space {
+            // 毎回同じ点ばかり選ばないように，空きの多い上位からランダムに何個か取ってくる
+            let mut candidates = free_space.top(FREE_SPACE_POOL_SIZE);
+            candidates.shuffle(rng);
+            neighbor_empty_p_bh.extend(candidates.into_iter().take(FREE_SPACE_CANDIDATE_SIZE));
+        } else {
+            // empty_check_num個を上限として空きマスをすべてのstepに対し順番に見ていく
+            let empty_check_num = rng.gen_range(5, 15);
+            for (p_idx, &step) in steps.iter().enumerate() {
+                // 残りがwidth_of_search個になったら，あまり後ろを見すぎても微妙なので辞める
+                if steps.len() - (p_idx + 1) > width_of_search {break;}
+                // 左右上下いずれかに空きマスがたくさんあるか見る
+                let mut max_empty_dist = 0;
+                for &(dx, dy) in &DIJ {
+                    let mut x = step.0;
+                    let mut y = step.1;
+                    // empty_check_num回ずつ試す
+                    let mut empty_dist = 0;
+                    for i in 0..empty_check_num {
+                        x = x.wrapping_add(dx);
+                        y = y.wrapping_add(dy);
+                        // 自身が1x2/2x1で隣が自身の片割れの場合は行かせてあげる
+                        if i == 0 && x < input.h && y < input.w && input.tiles[x][y] == input.tiles[step.0][step.1] {continue;}
+                        // x, yが範囲外になるか，すでに見た場所にたどり着くなら現時点のempty_distでmaxより大きいか調べる
+                        if x >= input.h || y >= input.w || seen[input.tiles[x][y]] {break;}
+                        else {empty_dist += input.ps[x][y];} // 得点の高い空きマスを優先したい
+                    }
+                    if empty_dist > max_empty_dist {
+                        max_empty_dist = empty_dist;
+                    }
+                }
+                // どれかの方向で一番長い距離行けたやつとともに，pのインデックスを保存する
+                neighbor_empty_p_bh.push((max_empty_dist, p_idx));
+            }
+        }
+        // 2つ未満なら諦めて普通に全体から探す
+        if neighbor_empty_p_bh.len() < 2 {
+            p1_idx = rng.gen_range(0, steps.len().saturating_sub(width_of_search).max(1));
+            p2_idx = rng.gen_range(p1_idx + 1, std::cmp::min(p1_idx + width_of_search, steps.len()).max(p1_idx + 2));
+            p2_idx = p2_idx.min(steps.len() - 1);
+        } else {
+            // とりあえずp1は空きマス数最大のやつを取ってくる
+            p1_idx = neighbor_empty_p_bh.pop().unwrap().1;
+            // 2番目も空きの多いやつにするか，全体から近いところを探すかテキトーにやる
+            if rng.gen_bool(0.15) {
+                p2_idx = neighbor_empty_p_bh.pop().unwrap().1;
+                // 2つ取ってもまだあるなら，確率的に変更する
+                let mut cnt = 0;
+                while let Some(neighbor_empty_p) = neighbor_empty_p_bh.pop() {
+                    // 変更を採用する確率
+                    if rng.gen_bool(0.15) && cnt < 4 {
+                        // p1, p2のどちらかを変える確率
+                        if rng.gen_bool(0.3) {
+                            p1_idx = neighbor_empty_p.1;
+                        } else {
+                            p2_idx = neighbor_empty_p.1;
+                        }
+                        cnt += 1;
+                    } else {
+                        break;
+                    }
+                }
+            } else if p1_idx + 1 < steps.len() {
+                // 2番目を全体から探すバージョン
+                p2_idx = rng.gen_range(p1_idx + 1, std::cmp::min(p1_idx + width_of_search, steps.len()));
+            } else {
+                // p1が経路の最後だったら一つ手前と入れ替える
+                p2_idx = p1_idx - 1;
+            }
+            // p1>p2ならスワップせねばならない
+            if p1_idx > p2_idx {
+                std::mem::swap(&mut p1_idx, &mut p2_idx);
+            }
+        }
+    }
+    (p1_idx, p2_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iteration_bounded_schedule_matches_time_bounded_curve() {
+        // 初期解2つ・合計200反復: 1つあたり100反復
+        let config = AnnealingConfig { solution_size: 2, max_iterations: Some(200), time_limit: f64::INFINITY, ..Default::default() };
+        let budget = Budget::new(config.time_limit, &phase_plan(&config));
+        let n = config.max_iterations.unwrap() / config.solution_size;
+        let ts: Vec<Vec<f64>> = (1..=config.solution_size).map(|state_iter| (1..=n).map(|iter| schedule_progress(&budget, Some(n), state_iter, iter)).collect()).collect();
+        // 1つ目は0から1まで
+        assert_eq!((ts[0][0], ts[0][n - 1]), (0.01, 1.0));
+        // 2つ目は時間で止めるときと同じく半分から1まで (最初からやり直さない)
+        assert_eq!((ts[1][0], ts[1][n - 1]), (0.505, 1.0));
+        for t in &ts {
+            assert!(t.windows(2).all(|w| w[0] < w[1]));
+        }
+        // 時間で止めるときの配り方も，初期解ごとの焼きなましは同じ重み
+        let plan = phase_plan(&AnnealingConfig { solution_size: 2, ..Default::default() });
+        let anneal: Vec<f64> = plan.iter().filter(|phase| phase.0 == "anneal").map(|phase| phase.1).collect();
+        assert_eq!(anneal.len(), 2);
+        assert_eq!(anneal[0], anneal[1]);
+    }
+}
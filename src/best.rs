@@ -0,0 +1,108 @@
+use crate::generator::to_input_string;
+use crate::scorer::compute_score;
+use crate::*;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// 盤面ごとのこれまでの最良解を，ディレクトリの下に1盤面1ファイル (<盤面のハッシュ>.txt) で持っておく
+/// ベンチで得点を「最良解に対して何割か」で比べるために使う
+pub struct BestKnown {
+    dir: PathBuf,
+}
+
+/// 1盤面分の最良解
+/// strategy: どの解法で見つけたか
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestEntry {
+    pub score: i32,
+    pub strategy: String,
+    pub output: Output,
+}
+
+#[derive(Debug)]
+pub enum BestError {
+    /// ファイルが読み書きできない
+    Io(PathBuf, std::io::Error),
+    /// ファイルの中身が読めないか，経路が盤面と合わない
+    Format(PathBuf, String),
+}
+
+impl fmt::Display for BestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BestError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            BestError::Format(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+impl std::error::Error for BestError {}
+
+/// 盤面のハッシュ (入力形式の文字列のFNV-1a)
+/// 実行ごと・Rustのバージョンごとに変わっては困るのでstdのHasherは使わない
+pub fn instance_hash(input: &Input) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in to_input_string(input).bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// 最良解に対する得点の比 (最良解が0点なら1とする)
+pub fn relative_score(score: i32, best_score: i32) -> f64 {
+    if best_score <= 0 {
+        1.0
+    } else {
+        score as f64 / best_score as f64
+    }
+}
+
+impl BestKnown {
+    /// dirがなければ最初に書き込むときに作る
+    pub fn open(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf() }
+    }
+
+    fn path_of(&self, input: &Input) -> PathBuf {
+        self.dir.join(format!("{:016x}.txt", instance_hash(input)))
+    }
+
+    /// その盤面の最良解 (まだなければNone)
+    /// 書いてある経路をscorerで計算し直して，得点が合わなければエラーにする
+    pub fn get(&self, input: &Input) -> Result<Option<BestEntry>, BestError> {
+        let path = self.path_of(input);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let src = std::fs::read_to_string(&path).map_err(|e| BestError::Io(path.clone(), e))?;
+        let error = |msg: String| BestError::Format(path.clone(), msg);
+        let get = |key: &str| {
+            src.lines()
+                .find_map(|line| line.split_once(' ').filter(|(k, _)| *k == key).map(|(_, v)| v.trim().to_string()))
+                .ok_or_else(|| error(format!("missing `{}`", key)))
+        };
+        let score = get("score")?;
+        let score = score.parse().map_err(|_| error(format!("invalid score `{}`", score)))?;
+        let entry = BestEntry { score, strategy: get("strategy")?, output: get("output").unwrap_or_default() };
+        match compute_score(input, &entry.output) {
+            Ok(actual) if actual == entry.score => Ok(Some(entry)),
+            Ok(actual) => Err(error(format!("recorded score {} but the output scores {}", entry.score, actual))),
+            Err(e) => Err(error(format!("invalid output: {}", e))),
+        }
+    }
+
+    /// 最良解を超えていたら書き換えてtrueを返す
+    /// 出力はscorerで得点を計算し直すので，不正な出力なら書き込まずにエラーにする
+    pub fn update(&self, input: &Input, strategy: &str, output: &str) -> Result<bool, BestError> {
+        let path = self.path_of(input);
+        let score = compute_score(input, output).map_err(|e| BestError::Format(path.clone(), format!("invalid output: {}", e)))?;
+        if self.get(input)?.is_some_and(|best| best.score >= score) {
+            return Ok(false);
+        }
+        std::fs::create_dir_all(&self.dir).map_err(|e| BestError::Io(self.dir.clone(), e))?;
+        let src = format!("score {}\nstrategy {}\noutput {}\n", score, strategy, output.trim());
+        write_atomic(&path, &src).map_err(|(path, e)| BestError::Io(path, e))?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,192 @@
+#![allow(non_snake_case)]
+
+// testcases/ 以下(や引数で渡したファイル・ディレクトリ)のケースをまとめて解き，スコアを並べる
+// cargo run --release --bin bench -- [パス...] [--tl 秒] [--bb] [--strategy annealing|mcts|chokudai|genetic|lns|exact] [--tail random|greedy|warnsdorff|lookahead<d>] [--rollouts n] [--warnsdorff] [--region ブロックの大きさ] [--solutions n] [--diverse 辺の数] [--free-space] [--prefix 確率] [--regrow 確率] [--polish 割合] [--db ディレクトリ]
+// --db を付けると盤面ごとの最良解をそこに記録し，最良解に対する得点の比も表示する
+
+use ahc002::annealing::{self, AnnealingConfig};
+use ahc002::best::{relative_score, BestKnown};
+use ahc002::dfs::DfsOrder;
+use ahc002::engine::DfsLimit;
+use ahc002::exact::solve_exact;
+use ahc002::genetic::{self, GeneticConfig};
+use ahc002::lns::{self, LnsConfig};
+use ahc002::mcts::{self, MctsConfig};
+use ahc002::search::chokudaiSearch;
+use ahc002::tail::TailPolicy;
+use ahc002::testcase;
+use ahc002::*;
+
+// 選んだ解法で解く (得点, 出力)
+fn solve(strategy: &str, input: &Input, config: &AnnealingConfig) -> (i32, Output) {
+    match strategy {
+        "annealing" => annealing::solve(input, config),
+        "mcts" => {
+            let mcts_config = MctsConfig { time_limit: config.time_limit, ..Default::default() };
+            let (state, report) = mcts::solve(input, State::new(input, !0, input.s), &mcts_config);
+            let mean_visits = report.root_visits.iter().sum::<usize>() as f64 / report.root_visits.len().max(1) as f64;
+            eprintln!(
+                "mcts: steps {}, playouts {}, nodes {}, max depth {}, mean visits of chosen child {:.1}",
+                report.steps, report.playouts, report.nodes, report.max_depth, mean_visits
+            );
+            (state.game_score_, state.output_)
+        }
+        "chokudai" => {
+            let state = chokudaiSearch(input, &State::new(input, !0, input.s), 3, None, Some(config.time_limit));
+            (state.game_score_, state.output_)
+        }
+        "genetic" => {
+            let genetic_config = GeneticConfig { time_limit: config.time_limit, annealing: config.clone(), ..Default::default() };
+            genetic::solve(input, &genetic_config)
+        }
+        "lns" => {
+            let lns_config = LnsConfig { time_limit: config.time_limit, annealing: config.clone(), ..Default::default() };
+            lns::solve(input, &lns_config)
+        }
+        // 小さい盤面用 (時間内に探索しきれなければそれまでの最善)
+        "exact" => {
+            let solution = solve_exact(input, DfsLimit::time(config.time_limit));
+            if !solution.optimal {
+                eprintln!("exact: not proved optimal within the time limit");
+            }
+            (solution.score, solution.output)
+        }
+        _ => panic!("unknown strategy `{}`", strategy),
+    }
+}
+
+// --tailに渡す名前 (TailPolicy::parseの逆)
+fn tail_name(policy: TailPolicy) -> String {
+    match policy {
+        TailPolicy::Random => "random".to_string(),
+        TailPolicy::Greedy => "greedy".to_string(),
+        TailPolicy::Warnsdorff => "warnsdorff".to_string(),
+        TailPolicy::Lookahead(depth) => format!("lookahead{}", depth),
+    }
+}
+
+// 最良解に記録する解法の名前
+// AnnealingConfigを使う解法は，既定値から変えた設定を "annealing+bb+tail=warnsdorff+polish=0.1" のように付ける (--tlは付けない)
+fn strategy_name(strategy: &str, config: &AnnealingConfig) -> String {
+    let mut name = strategy.to_string();
+    if !["annealing", "genetic", "lns"].contains(&strategy) {
+        return name;
+    }
+    let default = AnnealingConfig::default();
+    let mut settings = vec![];
+    if config.branch_and_bound {
+        settings.push("bb".to_string());
+    }
+    if config.tail_policy != default.tail_policy {
+        settings.push(format!("tail={}", tail_name(config.tail_policy)));
+    }
+    if config.tail_rollouts != default.tail_rollouts {
+        settings.push(format!("rollouts={}", config.tail_rollouts));
+    }
+    if config.dfs_orders != default.dfs_orders {
+        settings.push("warnsdorff".to_string());
+    }
+    if config.solution_size != default.solution_size {
+        settings.push(format!("solutions={}", config.solution_size));
+    }
+    if config.initial_min_distance != default.initial_min_distance {
+        settings.push(format!("diverse={}", config.initial_min_distance));
+    }
+    if let Some(block_size) = config.region_block_size {
+        settings.push(format!("region={}", block_size));
+    }
+    if config.free_space_map {
+        settings.push("free_space".to_string());
+    }
+    if config.prefix_rebuild_prob != default.prefix_rebuild_prob {
+        settings.push(format!("prefix={}", config.prefix_rebuild_prob));
+    }
+    if config.tail_regrow_prob != default.tail_regrow_prob {
+        settings.push(format!("regrow={}", config.tail_regrow_prob));
+    }
+    if config.polish_fraction != default.polish_fraction {
+        settings.push(format!("polish={}", config.polish_fraction));
+    }
+    for setting in settings {
+        name += "+";
+        name += &setting;
+    }
+    name
+}
+
+fn main() {
+    let mut paths = vec![];
+    let mut config = AnnealingConfig::default();
+    let mut db = None;
+    let mut strategy = "annealing".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tl" => config.time_limit = args.next().and_then(|tl| tl.parse().ok()).expect("--tl needs seconds"),
+            "--bb" => config.branch_and_bound = true,
+            "--strategy" => strategy = args.next().expect("--strategy needs a name"),
+            "--tail" => config.tail_policy = args.next().and_then(|name| TailPolicy::parse(&name)).expect("--tail needs a policy"),
+            "--rollouts" => config.tail_rollouts = args.next().and_then(|n| n.parse().ok()).expect("--rollouts needs a number"),
+            // 初期解のDFSに固定順の8通りに加えてWarnsdorff順も使う
+            "--warnsdorff" => config.dfs_orders.push(DfsOrder::Warnsdorff),
+            "--solutions" => config.solution_size = args.next().and_then(|n| n.parse().ok()).expect("--solutions needs a number"),
+            // 初期解同士が片方にしかない辺をこの数以上もつようにする
+            "--diverse" => config.initial_min_distance = args.next().and_then(|d| d.parse().ok()).expect("--diverse needs a distance"),
+            "--region" => config.region_block_size = Some(args.next().and_then(|b| b.parse().ok()).expect("--region needs a block size")),
+            "--free-space" => config.free_space_map = true,
+            "--prefix" => config.prefix_rebuild_prob = args.next().and_then(|p| p.parse().ok()).expect("--prefix needs a probability"),
+            "--regrow" => config.tail_regrow_prob = args.next().and_then(|p| p.parse().ok()).expect("--regrow needs a probability"),
+            "--polish" => config.polish_fraction = args.next().and_then(|p| p.parse().ok()).expect("--polish needs a fraction"),
+            "--db" => db = Some(BestKnown::open(args.next().expect("--db needs a directory"))),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        paths.push("testcases".to_string());
+    }
+
+    let mut cases = vec![];
+    for path in &paths {
+        match testcase::load(path) {
+            Ok(loaded) => cases.extend(loaded),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let strategy_name = strategy_name(&strategy, &config);
+    let mut total = 0i64;
+    let mut total_relative = 0.0;
+    for case in &cases {
+        let start_time = get_time();
+        let (score, output) = solve(&strategy, &case.input, &config);
+        let elapsed = get_time() - start_time;
+        total += score as i64;
+        let db = match &db {
+            Some(db) => db,
+            None => {
+                println!("{}\tscore: {}\ttime: {:.3}", case.name, score, elapsed);
+                continue;
+            }
+        };
+        // 今回の結果も含めた最良解と比べる
+        let result = db.update(&case.input, &strategy_name, &output).and_then(|_| db.get(&case.input));
+        let best_score = match result {
+            Ok(best) => best.map_or(score, |best| best.score),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        let relative = relative_score(score, best_score);
+        total_relative += relative;
+        println!("{}\tscore: {}\tbest: {}\trelative: {:.4}\ttime: {:.3}", case.name, score, best_score, relative, elapsed);
+    }
+    if db.is_some() {
+        println!("cases: {}\ttotal: {}\tmean relative: {:.4}", cases.len(), total, total_relative / cases.len().max(1) as f64);
+    } else {
+        println!("cases: {}\ttotal: {}", cases.len(), total);
+    }
+}
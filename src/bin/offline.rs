@@ -0,0 +1,45 @@
+#![allow(non_snake_case)]
+
+// 1つの盤面を時間をかけて焼き続け，途中経過をファイルに書き出す (提出用ではない)
+// cargo run --release --bin offline -- [--checkpoint ファイル] [--interval 秒] [--target 得点] [--wall 秒] [--round 反復回数] [--bb] < 入力
+// 途中で止めても，同じ --checkpoint を指定すれば続きから再開する
+
+use ahc002::offline::{run, OfflineConfig};
+use ahc002::*;
+use std::io::Read;
+
+fn main() {
+    let mut src = String::new();
+    std::io::stdin().read_to_string(&mut src).unwrap();
+    let input = match Input::parse(&src) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("invalid input: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut config = OfflineConfig::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{} needs a value", arg));
+        match arg.as_str() {
+            "--checkpoint" => config.checkpoint_path = value().into(),
+            "--interval" => config.checkpoint_interval = value().parse().expect("--interval needs seconds"),
+            "--target" => config.target_score = Some(value().parse().expect("--target needs a score")),
+            "--wall" => config.wall_limit = Some(value().parse().expect("--wall needs seconds")),
+            "--round" => config.round_iterations = value().parse().expect("--round needs a number"),
+            "--bb" => config.annealing.branch_and_bound = true,
+            _ => panic!("unknown argument `{}`", arg),
+        }
+    }
+    match run(&input, &config) {
+        Ok((best_score, best_output)) => {
+            println!("{}", best_output);
+            eprintln!("score: {}", best_score);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
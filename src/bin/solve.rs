@@ -0,0 +1,66 @@
+#![allow(non_snake_case)]
+
+// ライブラリ(src/lib.rs)の焼きなましを標準入出力で動かす
+// 提出用ではなく，ローカルで解法を試すためのもの
+// 盤面の大きさは入力から推定するので50x50以外の盤面でも動く
+// 焼きなましがpanicしても，--deadline <秒> を過ぎても返ってこなくても，それまでの最良解を出力する
+// cargo run --release --bin solve -- [--bb] [--init ファイル] [--deadline 秒] < 入力
+
+use ahc002::annealing::{solve, AnnealingConfig};
+use ahc002::safety::{guard, spawn_watchdog, SafetyNet};
+use ahc002::*;
+use std::io::Read;
+
+const USAGE: &str = "usage: solve [--bb] [--init <file>] [--deadline <seconds>] < input";
+
+// 引数がおかしければ使い方を出して終わる
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{}\n{}", msg, USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    get_time();
+    let mut config = AnnealingConfig::default();
+    let mut init_path = None;
+    let mut deadline = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // --bb で繋ぎ直しを分枝限定法でやる
+            "--bb" => config.branch_and_bound = true,
+            // --init <ファイル> で以前の出力を初期解に加える
+            "--init" => init_path = Some(args.next().unwrap_or_else(|| usage_error("--init needs a file"))),
+            // --deadline <秒> でこの時刻を過ぎたらその時点の最良解を出力して終わる
+            "--deadline" => deadline = Some(args.next().and_then(|d| d.parse::<f64>().ok()).unwrap_or_else(|| usage_error("--deadline needs seconds"))),
+            _ => usage_error(&format!("unknown argument `{}`", arg)),
+        }
+    }
+
+    let mut src = String::new();
+    std::io::stdin().read_to_string(&mut src).unwrap();
+    let input = match Input::parse(&src) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("invalid input: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(path) = init_path {
+        let output = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        if let Err(e) = State::fromOutput(&input, &output) {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+        config.initial_output = Some(output);
+    }
+    let net = SafetyNet::new(&input);
+    config.safety_net = Some(net.clone());
+    if let Some(deadline) = deadline {
+        spawn_watchdog(net.clone(), deadline);
+    }
+    let (best_score, _) = guard(&net, || solve(&input, &config));
+    net.emit();
+    eprintln!("score: {}", best_score);
+    eprintln!("time: {:.3}", get_time());
+}
@@ -0,0 +1,148 @@
+use crate::*;
+
+/// 制限時間を解法の段階(phase)ごとに配る
+/// 段階の並びと重みを最初に宣言しておき，段階を始めるたびに
+/// 「全体の締め切りまでの残り時間 × その段階の重み / まだ始めていない段階(自分を含む)の重みの和」を割り当てる
+/// 早く終わった(時間を使い切らなかった)段階の余りは，後ろの段階に重みの比で配り直されることになる
+/// 全体の締め切り(start + total)を過ぎる段階は作らないので，最後の段階が終われば出力する時間が残っている
+/// totalがINFINITYなら締め切りもINFINITYになる (反復回数で止めるとき)
+#[derive(Clone, Debug)]
+pub struct Budget {
+    start_time: f64,
+    deadline: f64,
+    phases: Vec<(String, f64)>,
+    next: usize,
+    phase_start: f64,
+    phase_end: f64,
+}
+
+impl Budget {
+    /// phases: (段階の名前, 重み) の並び (重みは負なら0とみなす)
+    pub fn new(total: f64, phases: &[(&str, f64)]) -> Self {
+        let start_time = get_time();
+        Self {
+            start_time,
+            deadline: start_time + total,
+            phases: phases.iter().map(|&(name, weight)| (name.to_string(), weight.max(0.0))).collect(),
+            next: 0,
+            phase_start: start_time,
+            phase_end: start_time,
+        }
+    }
+
+    /// 段階nameを始め，その段階の締め切り(get_timeの値)を返す
+    /// 宣言した並びでnameより前のまだ始めていない段階は飛ばす (その分の時間はnameから後ろに配り直される)
+    /// nameがこの先の並びになければpanicする (段階の取り違えを見つけるため)
+    pub fn begin(&mut self, name: &str) -> f64 {
+        let skip = self.phases[self.next..].iter().position(|phase| phase.0 == name);
+        self.next += skip.unwrap_or_else(|| panic!("phase `{}` is not ahead in the plan", name));
+        let rest: f64 = self.phases[self.next..].iter().map(|phase| phase.1).sum();
+        let weight = self.phases[self.next].1;
+        self.next += 1;
+        self.phase_start = get_time();
+        let remaining = (self.deadline - self.phase_start).max(0.0);
+        self.phase_end = if weight > 0.0 { self.phase_start + remaining * (weight / rest) } else { self.phase_start };
+        // 最後の段階は余りを全部使う (重みの丸めで時間を残さない)
+        if self.next == self.phases.len() && weight > 0.0 {
+            self.phase_end = self.deadline;
+        }
+        self.phase_end
+    }
+
+    /// 始めた時刻(get_timeの値)
+    pub fn start_time(&self) -> f64 {
+        self.start_time
+    }
+
+    /// 今の段階の締め切り
+    pub fn phase_end(&self) -> f64 {
+        self.phase_end
+    }
+
+    /// 今の段階の進み具合 (0~1)
+    pub fn progress(&self) -> f64 {
+        let length = self.phase_end - self.phase_start;
+        if length <= 0.0 {
+            1.0
+        } else {
+            ((get_time() - self.phase_start) / length).min(1.0)
+        }
+    }
+
+    /// 今の段階の時間を使い切ったか
+    pub fn phase_over(&self) -> bool {
+        get_time() >= self.phase_end
+    }
+
+    /// 全体の締め切りまでの残り時間(秒)
+    pub fn remaining(&self) -> f64 {
+        self.deadline - get_time()
+    }
+
+    /// 全体の締め切りを過ぎたか
+    pub fn over(&self) -> bool {
+        get_time() >= self.deadline
+    }
+
+    /// 始めてからの経過時間(秒)
+    pub fn elapsed(&self) -> f64 {
+        get_time() - self.start_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // get_timeは実時間なので，テストの中で経つ時間の分だけ余裕を見る
+    const EPS: f64 = 0.05;
+
+    #[test]
+    fn leftover_time_goes_to_later_phases() {
+        let mut budget = Budget::new(1.0, &[("a", 1.0), ("b", 1.0), ("c", 2.0)]);
+        let start = budget.start_time();
+        // 残り1秒のうち1/4
+        let a_end = budget.begin("a");
+        assert!((a_end - (start + 0.25)).abs() < EPS, "{}", a_end - start);
+        // aをすぐ終えたので，残りほぼ1秒をbとcで1:2に分ける
+        let b_start = get_time();
+        let b_end = budget.begin("b");
+        assert!((b_end - b_start - (budget.deadline - b_start) / 3.0).abs() < EPS, "{}", b_end - start);
+        assert!(b_end - start > 0.25 + EPS, "leftover of a was not redistributed: {}", b_end - start);
+    }
+
+    #[test]
+    fn last_phase_ends_at_deadline() {
+        let mut budget = Budget::new(1.0, &[("a", 1.0), ("b", 1.0), ("c", 0.5)]);
+        budget.begin("a");
+        budget.begin("b");
+        assert_eq!(budget.begin("c"), budget.start_time() + 1.0);
+        // 途中を飛ばしても最後の段階は締め切りまで
+        let mut budget = Budget::new(1.0, &[("a", 1.0), ("b", 1.0)]);
+        assert_eq!(budget.begin("b"), budget.start_time() + 1.0);
+    }
+
+    #[test]
+    fn zero_weight_phase_ends_immediately() {
+        let mut budget = Budget::new(1.0, &[("a", 0.0), ("b", 1.0)]);
+        let a_end = budget.begin("a");
+        assert!(a_end - budget.start_time() < EPS);
+        assert!(budget.phase_over());
+        assert_eq!(budget.begin("b"), budget.start_time() + 1.0);
+    }
+
+    #[test]
+    fn infinite_total_never_ends() {
+        let mut budget = Budget::new(f64::INFINITY, &[("a", 1.0), ("b", 1.0)]);
+        assert_eq!(budget.begin("a"), f64::INFINITY);
+        assert!(!budget.phase_over() && !budget.over());
+    }
+
+    #[test]
+    #[should_panic(expected = "phase `a` is not ahead in the plan")]
+    fn going_back_to_a_finished_phase_panics() {
+        let mut budget = Budget::new(1.0, &[("a", 1.0), ("b", 1.0)]);
+        budget.begin("b");
+        budget.begin("a");
+    }
+}
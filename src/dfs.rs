@@ -0,0 +1,93 @@
+use crate::engine::*;
+use crate::tail::onward_moves;
+use crate::topk::TopK;
+use crate::*;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// 初期解構築のDFSで子を見る順番
+/// Fixed(d): 方向dの順 (DIR_LISTのどれかを渡すと蛇腹状に塗りつぶしていく)
+/// Warnsdorff: 行き先から先に進める方向が少ない順，同じなら行き先の得点が高い順
+///   袋小路を先に踏むので，行き止まりを残さずハミルトン路に近い長い経路ができやすい
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DfsOrder {
+    Fixed([usize; 4]),
+    Warnsdorff,
+}
+
+/// DIR_LISTの8通りの固定順 (提出用の解法と同じ)
+pub fn fixed_dfs_orders() -> Vec<DfsOrder> {
+    DIR_LIST.iter().map(|&d| DfsOrder::Fixed(d)).collect()
+}
+
+// 初期解構築のDFSのフック
+// orderの順に見て，途中の経路のうちスコアの高いものをbest_actionsに残す
+struct FirstSolutionHooks<'a> {
+    order: DfsOrder,
+    best_actions: &'a mut TopK<Actions>,
+}
+
+impl DfsHooks<PathState> for FirstSolutionHooks<'_> {
+    fn evaluate(&mut self, _input: &Input, state: &PathState) {
+        // 注：actionsを毎回cloneして溜めるとすぐにMLEになる
+        // TopKは上位に入るときだけcloneする
+        if !state.actions.is_empty() {
+            self.best_actions.offer(state.score, &state.actions);
+        }
+    }
+
+    fn order(&mut self, input: &Input, state: &PathState, actions: &mut Actions) {
+        match self.order {
+            DfsOrder::Fixed(d) => actions.sort_by_key(|a| d.iter().position(|x| x == a)),
+            DfsOrder::Warnsdorff => actions.sort_by_key(|&a| {
+                let i = state.pos.0.wrapping_add(DIJ[a].0);
+                let j = state.pos.1.wrapping_add(DIJ[a].1);
+                (onward_moves(input, &state.seen, i, j), -input.ps[i][j])
+            }),
+        }
+    }
+}
+
+// 初期解構築のためのDFS
+// stateからorderの順に進み，途中の経路のうちスコアの高いものをbest_actionsに残す
+pub fn dfs_making_first_solution(input: &Input, order: DfsOrder, state: PathState, best_actions: &mut TopK<Actions>, limit: DfsLimit) -> DfsStatus {
+    let mut hooks = FirstSolutionHooks { order, best_actions };
+    DfsEngine::new(state).run(input, &mut hooks, limit)
+}
+
+// p1->p2の経路探索のDFSのフック
+// 方向を見る順番はノードごとにdir_listから適当に取ってくる
+struct DestinationHooks<'a> {
+    rng: &'a mut Pcg64Mcg,
+    dir_list: &'a [[usize; 4]],
+    goal: (usize, usize),
+    action_top: &'a mut TopK<Actions>,
+}
+
+impl DfsHooks<PathState> for DestinationHooks<'_> {
+    fn evaluate(&mut self, _input: &Input, state: &PathState) {
+        // p2へたどり着いたものを保存
+        if state.pos == self.goal {
+            self.action_top.offer(state.score, &state.actions);
+        }
+    }
+
+    fn prune(&mut self, _input: &Input, state: &PathState) -> bool {
+        // p2に着いたらそれより先には進まない (p2のタイルは踏んだので二度と戻ってこられない)
+        state.pos == self.goal
+    }
+
+    fn order(&mut self, _input: &Input, _state: &PathState, actions: &mut Actions) {
+        let d = &self.dir_list[self.rng.gen_range(0, self.dir_list.len())];
+        actions.sort_by_key(|a| d.iter().position(|x| x == a));
+    }
+}
+
+// p1->p2へたどり着く経路を探すためのdfs
+// stateはp1にいて，p1->p2の区間のseenを消した状態で渡す
+// (スコア, action)のうち上位のものをaction_topに格納する
+pub fn dfs_to_destination(rng: &mut Pcg64Mcg, input: &Input, dir_list: &[[usize;4]], state: PathState, goal: (usize, usize), action_top: &mut TopK<Actions>, limit: DfsLimit) -> DfsStatus {
+    let mut hooks = DestinationHooks { rng, dir_list, goal, action_top };
+    DfsEngine::new(state).run(input, &mut hooks, limit)
+}
@@ -0,0 +1,269 @@
+use crate::*;
+
+/// 明示的なスタックで回すDFSが扱う状態
+/// applyで1手進め，undoでその1手を取り消せること
+pub trait DfsState {
+    /// 今の状態から打てる行動をactionsに詰める
+    fn legal_actions(&self, input: &Input, actions: &mut Actions);
+    fn apply(&mut self, input: &Input, action: Action);
+    fn undo(&mut self, input: &Input, action: Action);
+}
+
+/// DFSの挙動を差し替えるためのフック
+/// ノードに入るたびに evaluate -> prune -> (枝刈りされなければ) legal_actions -> order の順に呼ぶ
+pub trait DfsHooks<S> {
+    /// ノードに入ったときに呼ぶ (葉や途中経路の評価・記録はここでやる)
+    fn evaluate(&mut self, _input: &Input, _state: &S) {}
+    /// trueを返すとこのノードの子は見ない
+    fn prune(&mut self, _input: &Input, _state: &S) -> bool {
+        false
+    }
+    /// 子を見る順番を決める (先頭から順に探索する)
+    fn order(&mut self, _input: &Input, _state: &S, _actions: &mut Actions) {}
+}
+
+/// DFSを打ち切る条件
+/// max_nodes: runを呼んでから訪れるノード数の上限
+/// time_limit: runを呼んでからの秒数の上限
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DfsLimit {
+    pub max_nodes: Option<usize>,
+    pub time_limit: Option<f64>,
+}
+
+impl DfsLimit {
+    pub fn time(tl: f64) -> Self {
+        Self { max_nodes: None, time_limit: Some(tl) }
+    }
+
+    pub fn nodes(max_nodes: usize) -> Self {
+        Self { max_nodes: Some(max_nodes), time_limit: None }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DfsStatus {
+    /// 探索木を全部見終わった
+    Finished,
+    /// 打ち切り条件に引っかかって止まった (もう一度runを呼ぶと続きから探索する)
+    Suspended,
+}
+
+// スタックの1段分
+// actions: このノードで試す行動, next: 次に試すactionsの添字, via: このノードに来るのに使った行動
+struct Frame {
+    actions: Actions,
+    next: usize,
+    via: Option<Action>,
+}
+
+/// 再帰を使わないDFS
+/// 盤面全体を歩くと深さが2500近くになり，再帰だとスタックが心配なのと途中で止めて再開できないので，
+/// 自前のスタックに「まだ試していない行動」を積んで回す
+/// stateは常に探索中のノードの状態になっている (runから戻ったときも)
+pub struct DfsEngine<S> {
+    pub state: S,
+    stack: Vec<Frame>,
+    started: bool,
+    /// これまでに訪れたノードの総数
+    pub node_cnt: usize,
+}
+
+impl<S: DfsState> DfsEngine<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            stack: vec![],
+            started: false,
+            node_cnt: 0,
+        }
+    }
+
+    /// 探索し終わったか
+    pub fn is_finished(&self) -> bool {
+        self.started && self.stack.is_empty()
+    }
+
+    /// limitに引っかかるか探索し終わるまでDFSを進める
+    pub fn run<H: DfsHooks<S>>(&mut self, input: &Input, hooks: &mut H, limit: DfsLimit) -> DfsStatus {
+        let start_time = get_time();
+        let start_cnt = self.node_cnt;
+        if !self.started {
+            self.started = true;
+            self.enter(input, hooks, None);
+        }
+        while let Some(frame) = self.stack.last_mut() {
+            if frame.next < frame.actions.len() {
+                // 打ち切り判定は新しいノードに入る前にやる (再開したときにそのまま続きから入れるように)
+                let visited = self.node_cnt - start_cnt;
+                if limit.max_nodes.is_some_and(|max_nodes| visited >= max_nodes)
+                    || limit.time_limit.is_some_and(|tl| get_time() - start_time > tl)
+                {
+                    return DfsStatus::Suspended;
+                }
+                // 行きがけの更新
+                let action = frame.actions[frame.next];
+                frame.next += 1;
+                self.state.apply(input, action);
+                self.enter(input, hooks, Some(action));
+            } else {
+                // 帰りがけに戻す
+                let frame = self.stack.pop().unwrap();
+                if let Some(action) = frame.via {
+                    self.state.undo(input, action);
+                }
+            }
+        }
+        DfsStatus::Finished
+    }
+
+    // 今のstateのノードに入り，子を積む
+    fn enter<H: DfsHooks<S>>(&mut self, input: &Input, hooks: &mut H, via: Option<Action>) {
+        self.node_cnt += 1;
+        hooks.evaluate(input, &self.state);
+        let mut actions = vec![];
+        if !hooks.prune(input, &self.state) {
+            self.state.legal_actions(input, &mut actions);
+            hooks.order(input, &self.state, &mut actions);
+        }
+        self.stack.push(Frame { actions, next: 0, via });
+    }
+}
+
+/// DFS用の軽い経路の状態
+/// TileStateと違ってoutput_やsteps_を持たないのでundoが安い
+/// pos: 現在位置, seen: タイルを踏んだかどうか, actions: ここまでの行動, score: ここまでの得点
+#[derive(Clone, Debug)]
+pub struct PathState {
+    pub pos: (usize, usize),
+    pub seen: Vec<bool>,
+    pub actions: Actions,
+    pub score: i32,
+}
+
+impl PathState {
+    /// posから始める (posのタイルは踏んだことにする)
+    pub fn new(input: &Input, pos: (usize, usize), mut seen: Vec<bool>, score: i32) -> Self {
+        seen[input.tiles[pos.0][pos.1]] = true;
+        Self { pos, seen, actions: vec![], score }
+    }
+}
+
+impl DfsState for PathState {
+    fn legal_actions(&self, input: &Input, actions: &mut Actions) {
+        for (action, &(dx, dy)) in DIJ.iter().enumerate() {
+            // 範囲外参照を防ぐ
+            let to_x = self.pos.0.wrapping_add(dx);
+            let to_y = self.pos.1.wrapping_add(dy);
+            if to_x < input.h && to_y < input.w && !self.seen[input.tiles[to_x][to_y]] {
+                actions.push(action);
+            }
+        }
+    }
+
+    fn apply(&mut self, input: &Input, action: Action) {
+        self.pos.0 = self.pos.0.wrapping_add(DIJ[action].0);
+        self.pos.1 = self.pos.1.wrapping_add(DIJ[action].1);
+        self.seen[input.tiles[self.pos.0][self.pos.1]] = true;
+        self.actions.push(action);
+        self.score += input.ps[self.pos.0][self.pos.1];
+    }
+
+    fn undo(&mut self, input: &Input, action: Action) {
+        self.seen[input.tiles[self.pos.0][self.pos.1]] = false;
+        self.actions.pop();
+        self.score -= input.ps[self.pos.0][self.pos.1];
+        self.pos.0 = self.pos.0.wrapping_sub(DIJ[action].0);
+        self.pos.1 = self.pos.1.wrapping_sub(DIJ[action].1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+
+    // 入ったノードの行動列を順に記録し，深さdepth_limitより深いところは枝刈りする
+    struct Recorder {
+        visited: Vec<Actions>,
+        depth_limit: usize,
+    }
+
+    impl DfsHooks<PathState> for Recorder {
+        fn evaluate(&mut self, _input: &Input, state: &PathState) {
+            self.visited.push(state.actions.clone());
+        }
+
+        fn prune(&mut self, _input: &Input, state: &PathState) -> bool {
+            state.actions.len() >= self.depth_limit
+        }
+    }
+
+    fn engine(input: &Input) -> DfsEngine<PathState> {
+        DfsEngine::new(PathState::new(input, input.s, vec![false; input.tile_count()], 0))
+    }
+
+    fn recorder(depth_limit: usize) -> Recorder {
+        Recorder { visited: vec![], depth_limit }
+    }
+
+    #[test]
+    fn resumed_run_visits_the_same_nodes() {
+        let input = generate(0, 4, 4);
+        let mut whole = recorder(usize::MAX);
+        let mut dfs = engine(&input);
+        assert_eq!(dfs.run(&input, &mut whole, DfsLimit::default()), DfsStatus::Finished);
+        assert!(dfs.is_finished());
+        assert_eq!(dfs.node_cnt, whole.visited.len());
+
+        for chunk in [1, 2, 3, 7, 100] {
+            let mut resumed = recorder(usize::MAX);
+            let mut dfs = engine(&input);
+            let mut runs = 0;
+            while dfs.run(&input, &mut resumed, DfsLimit::nodes(chunk)) == DfsStatus::Suspended {
+                runs += 1;
+                // 止まったときのstateは最後に入ったノードかその祖先 (帰りがけに戻した後で止まることもある)
+                assert!(resumed.visited.last().unwrap().starts_with(&dfs.state.actions), "chunk {}", chunk);
+                assert!(!dfs.is_finished());
+            }
+            assert_eq!(resumed.visited, whole.visited, "chunk {}", chunk);
+            assert_eq!(runs, (whole.visited.len() - 1) / chunk, "chunk {}", chunk);
+            // 全部見終わったら根に戻っている
+            assert!(dfs.is_finished());
+            assert_eq!((dfs.state.pos, dfs.state.score), (input.s, 0));
+            assert!(dfs.state.actions.is_empty());
+            // 見終わった後にrunしても何もしない
+            assert_eq!(dfs.run(&input, &mut resumed, DfsLimit::nodes(chunk)), DfsStatus::Finished);
+            assert_eq!(resumed.visited.len(), whole.visited.len());
+        }
+    }
+
+    #[test]
+    fn max_nodes_counts_nodes_per_run() {
+        let input = generate(1, 5, 5);
+        let mut hooks = recorder(usize::MAX);
+        let mut dfs = engine(&input);
+        // 1回目は根も数える
+        assert_eq!(dfs.run(&input, &mut hooks, DfsLimit::nodes(10)), DfsStatus::Suspended);
+        assert_eq!((dfs.node_cnt, hooks.visited.len()), (10, 10));
+        assert_eq!(dfs.run(&input, &mut hooks, DfsLimit::nodes(5)), DfsStatus::Suspended);
+        assert_eq!((dfs.node_cnt, hooks.visited.len()), (15, 15));
+        // 0なら1ノードも進めない
+        assert_eq!(dfs.run(&input, &mut hooks, DfsLimit::nodes(0)), DfsStatus::Suspended);
+        assert_eq!(dfs.node_cnt, 15);
+    }
+
+    #[test]
+    fn pruned_nodes_have_no_children() {
+        let input = generate(2, 5, 5);
+        let mut whole = recorder(usize::MAX);
+        engine(&input).run(&input, &mut whole, DfsLimit::default());
+        let mut pruned = recorder(2);
+        let mut dfs = engine(&input);
+        assert_eq!(dfs.run(&input, &mut pruned, DfsLimit::default()), DfsStatus::Finished);
+        // 深さ2までのノードだけを，枝刈りしないときと同じ順で訪れる
+        let shallow: Vec<Actions> = whole.visited.into_iter().filter(|actions| actions.len() <= 2).collect();
+        assert_eq!(pruned.visited, shallow);
+        assert_eq!(dfs.node_cnt, pruned.visited.len());
+    }
+}
@@ -0,0 +1,38 @@
+// 小さい盤面の厳密解
+// ヒューリスティックが最適解からどれくらい離れているかを測るために使う (テスト・ベンチ用)
+
+use crate::engine::DfsLimit;
+use crate::segment::optimize_tail;
+use crate::*;
+
+/// 厳密解法の結果
+/// score: sの得点を含む経路全体の得点, output: 経路(LRUD)
+/// optimal: limitで打ち切られずに探索しきったか (falseならそれまでの最善)
+#[derive(Clone, Debug)]
+pub struct ExactSolution {
+    pub score: i32,
+    pub output: Output,
+    pub optimal: bool,
+}
+
+/// sから始まる経路のうち得点が最大のものを分枝限定法で求める (segment::optimize_tailをsからやるだけ)
+/// 6x6〜10x10くらいまでならすぐ終わる．大きい盤面ではlimitで打ち切ること
+pub fn solve_exact(input: &Input, limit: DfsLimit) -> ExactSolution {
+    let mut seen = vec![false; input.tile_count()];
+    seen[input.tiles[input.s.0][input.s.1]] = true;
+    let path = optimize_tail(input, &mut seen, input.s, limit);
+    ExactSolution {
+        score: input.ps[input.s.0][input.s.1] + path.score,
+        output: path.actions.iter().map(|&action| DIR[action]).collect(),
+        optimal: path.optimal,
+    }
+}
+
+/// 最適解に対して何割足りないか (0なら最適)
+pub fn optimality_gap(score: i32, optimum: i32) -> f64 {
+    if optimum <= 0 {
+        0.0
+    } else {
+        (optimum - score) as f64 / optimum as f64
+    }
+}
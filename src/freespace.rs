@@ -0,0 +1,147 @@
+// 経路のまわりの空き具合を差分更新で持っておく構造
+// 焼きなましの「近くに空きがたくさんあるところを見る」近傍は，毎回経路の全マスから4方向に空きマスを数えていた
+// ここでは経路上の各マスの空き具合を持っておき，繋ぎ変えた(お尻を伸ばした)ところのまわりだけ計算し直す
+
+use crate::*;
+use std::collections::BTreeSet;
+
+/// 空きマスを数える長さの上限 (もとの近傍のempty_check_numの最大)
+pub const FREE_RUN_CAP: usize = 14;
+
+/// 経路上のマスごとに「上下左右のどれかにまっすぐ進んだときに続く空きマスの得点の和」の最大を持つ
+/// (まっすぐFREE_RUN_CAPマスまで．自分のタイルの片割れは飛ばす)
+/// 空き具合の大きい順に経路上のマスを並べておき，上位の添字をO(k log L)で返す
+pub struct FreeSpaceMap {
+    seen: Vec<bool>,
+    path: Vec<(usize, usize)>,
+    // 経路上のマスなら何歩目か，そうでなければ!0
+    index_of: Vec<Vec<usize>>,
+    free: Vec<Vec<i32>>,
+    order: BTreeSet<(i32, (usize, usize))>,
+    // 計算し直すマスの重複を除く作業領域
+    stamp: u32,
+    cell_stamp: Vec<Vec<u32>>,
+}
+
+impl FreeSpaceMap {
+    pub fn new(input: &Input, state: &State) -> Self {
+        let mut map = Self {
+            seen: state.seen_.clone(),
+            path: state.steps_.clone(),
+            index_of: vec![vec![!0; input.w]; input.h],
+            free: vec![vec![0; input.w]; input.h],
+            order: BTreeSet::new(),
+            stamp: 0,
+            cell_stamp: vec![vec![0; input.w]; input.h],
+        };
+        for (idx, &(i, j)) in state.steps_.iter().enumerate() {
+            map.index_of[i][j] = idx;
+            map.free[i][j] = map.free_run(input, i, j);
+            map.order.insert((map.free[i][j], (i, j)));
+        }
+        map
+    }
+
+    // (i, j)から4方向にまっすぐ進んだときの空きマスの得点の和の最大
+    fn free_run(&self, input: &Input, i: usize, j: usize) -> i32 {
+        let mut best = 0;
+        for &(di, dj) in &DIJ {
+            let (mut x, mut y) = (i, j);
+            let mut sum = 0;
+            for k in 0..FREE_RUN_CAP {
+                x = x.wrapping_add(di);
+                y = y.wrapping_add(dj);
+                if k == 0 && x < input.h && y < input.w && input.tiles[x][y] == input.tiles[i][j] {
+                    continue;
+                }
+                if x >= input.h || y >= input.w || self.seen[input.tiles[x][y]] {
+                    break;
+                }
+                sum += input.ps[x][y];
+            }
+            best = best.max(sum);
+        }
+        best
+    }
+
+    /// 経路上のマスのうち空き具合の大きい順にk個 (空き具合, 添字)
+    pub fn top(&self, k: usize) -> Vec<(i32, usize)> {
+        self.order.iter().rev().take(k).map(|&(free, (i, j))| (free, self.index_of[i][j])).collect()
+    }
+
+    /// 経路上の添字idxのマスの空き具合
+    pub fn free_at(&self, idx: usize) -> i32 {
+        let (i, j) = self.path[idx];
+        self.free[i][j]
+    }
+
+    /// stateに合わせて更新する
+    /// from: stateの経路と持っている経路がfrom歩目まで同じであること (繋ぎ変えならp1_idx，お尻を伸ばしただけなら伸ばす前の長さ-1)
+    /// 同じところから先が違うところだけを書き換えるので，経路全体はコピーしない
+    pub fn sync(&mut self, input: &Input, state: &State, from: usize) {
+        let mut from = from.min(self.path.len() - 1).min(state.steps_.len() - 1);
+        // fromより先も同じならそこまで進める
+        while from + 1 < self.path.len().min(state.steps_.len()) && self.path[from + 1] == state.steps_[from + 1] {
+            from += 1;
+        }
+        // 経路から外れたマスを抜く
+        for &(i, j) in &self.path[from + 1..] {
+            self.order.remove(&(self.free[i][j], (i, j)));
+            self.index_of[i][j] = !0;
+        }
+        // 踏んだかどうかが変わったタイルのマス (経路が通っていない片割れも含む)
+        // 変わるのは外れたマスか新しく入ったマスのタイルだけなので，seenもそこだけ書き換える
+        let mut changed = vec![];
+        for &(i, j) in self.path[from + 1..].iter().chain(&state.steps_[from + 1..]) {
+            let tile = input.tiles[i][j];
+            if self.seen[tile] == state.seen_[tile] {
+                continue;
+            }
+            self.seen[tile] = state.seen_[tile];
+            changed.push((i, j));
+            for &(di, dj) in &DIJ {
+                let (x, y) = (i.wrapping_add(di), j.wrapping_add(dj));
+                if x < input.h && y < input.w && input.tiles[x][y] == tile {
+                    changed.push((x, y));
+                }
+            }
+        }
+        self.path.truncate(from + 1);
+        self.path.extend_from_slice(&state.steps_[from + 1..]);
+        // そのまわりを計算し直す ((i, j)をまっすぐ見通せるのは同じ行・列のFREE_RUN_CAPマス以内だけ)
+        self.stamp += 1;
+        let mut dirty = vec![];
+        for (i, j) in changed {
+            for &(di, dj) in &DIJ {
+                let (mut x, mut y) = (i, j);
+                for _ in 0..=FREE_RUN_CAP {
+                    if x >= input.h || y >= input.w {
+                        break;
+                    }
+                    if self.cell_stamp[x][y] != self.stamp {
+                        self.cell_stamp[x][y] = self.stamp;
+                        dirty.push((x, y));
+                    }
+                    x = x.wrapping_add(di);
+                    y = y.wrapping_add(dj);
+                }
+            }
+        }
+        // 新しく経路に入ったマス
+        for (idx, &(i, j)) in state.steps_.iter().enumerate().skip(from + 1) {
+            self.index_of[i][j] = idx;
+            if self.cell_stamp[i][j] != self.stamp {
+                self.cell_stamp[i][j] = self.stamp;
+                dirty.push((i, j));
+            }
+        }
+        for (i, j) in dirty {
+            if self.index_of[i][j] == !0 {
+                continue;
+            }
+            self.order.remove(&(self.free[i][j], (i, j)));
+            self.free[i][j] = self.free_run(input, i, j);
+            self.order.insert((self.free[i][j], (i, j)));
+        }
+    }
+}
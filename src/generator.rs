@@ -0,0 +1,48 @@
+use crate::*;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// seedから h x w の盤面の入力を作る (テストやベンチ用)
+/// 本番の生成方法に合わせて，各マスを左上から見て 1x1, 1x2, 2x1 のタイルのどれかで埋め，
+/// 得点は MIN_POINT..=MAX_POINT の一様乱数，sはランダムなマスにする
+/// 同じ(seed, h, w)からは常に同じ入力ができる
+pub fn generate(seed: u64, h: usize, w: usize) -> Input {
+    let mut rng = Pcg64Mcg::seed_from_u64(seed);
+    let mut tiles = vec![vec![!0; w]; h];
+    let mut M = 0;
+    for i in 0..h {
+        for j in 0..w {
+            if tiles[i][j] != !0 {
+                continue;
+            }
+            tiles[i][j] = M;
+            // 0: 1x1, 1: 右と繋げる, 2: 下と繋げる (繋げられなければ1x1)
+            match rng.gen_range(0, 3) {
+                1 if j + 1 < w && tiles[i][j + 1] == !0 => tiles[i][j + 1] = M,
+                2 if i + 1 < h => tiles[i + 1][j] = M,
+                _ => {}
+            }
+            M += 1;
+        }
+    }
+    let ps = (0..h)
+        .map(|_| (0..w).map(|_| rng.gen_range(input::MIN_POINT, input::MAX_POINT + 1)).collect())
+        .collect();
+    let s = (rng.gen_range(0, h), rng.gen_range(0, w));
+    Input::new(s, tiles, ps)
+}
+
+/// 問題の入力形式の文字列にする (Input::parseで読み戻せる)
+pub fn to_input_string(input: &Input) -> String {
+    let mut src = format!("{} {}\n", input.s.0, input.s.1);
+    for row in &input.tiles {
+        src += &row.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ");
+        src.push('\n');
+    }
+    for row in &input.ps {
+        src += &row.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ");
+        src.push('\n');
+    }
+    src
+}
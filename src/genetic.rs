@@ -0,0 +1,175 @@
+// 遺伝的アルゴリズム
+// 完成した経路を個体として集団で持ち，2つの経路の交叉と繋ぎ直し(焼きなましの近傍)の突然変異で改善していく
+
+use crate::annealing::{anneal_step, make_initial_states, AnnealingConfig};
+use crate::dfs::dfs_to_destination;
+use crate::engine::PathState;
+use crate::tail::extend_tail_with;
+use crate::topk::TopK;
+use crate::*;
+
+use std::collections::HashSet;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// 遺伝的アルゴリズムのパラメータ
+/// time_limit: 全体の制限時間(秒)
+/// max_generations: 世代数の上限 (Someなら時間を見ずにこの世代数だけ回すので結果が再現する)
+/// population_size: 集団の大きさ, offspring: 1世代で作る子の数
+/// tournament_size: 親を選ぶトーナメントの参加数
+/// mutation_rate: 子に突然変異を入れる確率, mutation_steps: 突然変異で繋ぎ直しを何回するか
+/// annealing: 初期集団の作り方(make_initial_states)と，繋ぎ直しの打ち切り条件・お尻の伸ばし方に使う
+///   annealing.solution_sizeは使わずpopulation_size個作る．足りない分はsからお尻を伸ばすだけの経路で埋める
+#[derive(Clone, Debug)]
+pub struct GeneticConfig {
+    pub time_limit: f64,
+    pub max_generations: Option<usize>,
+    pub population_size: usize,
+    pub offspring: usize,
+    pub tournament_size: usize,
+    pub mutation_rate: f64,
+    pub mutation_steps: usize,
+    pub annealing: AnnealingConfig,
+}
+
+impl Default for GeneticConfig {
+    fn default() -> Self {
+        Self {
+            time_limit: 1.9,
+            max_generations: None,
+            population_size: 16,
+            offspring: 16,
+            tournament_size: 3,
+            mutation_rate: 0.5,
+            mutation_steps: 20,
+            annealing: AnnealingConfig::default(),
+        }
+    }
+}
+
+/// aのprefixとbのsuffixを繋いだ子を作る
+/// (1) aとbが両方通るマスcを1つ選ぶ (どちらもsから始まるなら必ずある．なければNone)
+/// (2) aのsからcまでを採る
+/// (3) bのcより後ろのうち，(2)で踏んだタイルを踏まない一番長いsuffixを探す
+/// (4) cからそのsuffixの先頭へDFSで繋ぎ直す (隣ならそのまま繋ぐ)．繋がらなければNone
+/// 最後にお尻を伸ばせるなら伸ばしておく
+pub fn crossover(rng: &mut Pcg64Mcg, input: &Input, config: &AnnealingConfig, a: &State, b: &State) -> Option<State> {
+    // bの各マスが何歩目か
+    let mut index_in_b = vec![vec![None; input.w]; input.h];
+    for (idx, &(i, j)) in b.steps_.iter().enumerate() {
+        index_in_b[i][j] = Some(idx);
+    }
+    let shared: Vec<(usize, usize)> = a.steps_.iter().enumerate().filter_map(|(ia, &(i, j))| index_in_b[i][j].map(|ib| (ia, ib))).collect();
+    if shared.is_empty() {
+        return None;
+    }
+    let (ia, ib) = shared[rng.gen_range(0, shared.len())];
+
+    let mut seen = vec![false; input.tile_count()];
+    for &(i, j) in &a.steps_[..=ia] {
+        seen[input.tiles[i][j]] = true;
+    }
+    // bのsuffixの先頭 (ここから後ろはprefixと重ならない)
+    let mut k = ib + 1;
+    for m in ib + 1..b.steps_.len() {
+        let (i, j) = b.steps_[m];
+        if seen[input.tiles[i][j]] {
+            k = m + 1;
+        }
+    }
+
+    let mut child = State::new(input, !0, input.s);
+    child.advanceByOutput(input, &a.output_[..ia]);
+    if k < b.steps_.len() {
+        if k > ib + 1 {
+            // cからb[k]まで，prefixとsuffixのタイルを避けて繋ぐ
+            for &(i, j) in &b.steps_[k + 1..] {
+                seen[input.tiles[i][j]] = true;
+            }
+            let mut action_top = TopK::new(1);
+            let start = PathState::new(input, a.steps_[ia], seen, 0);
+            dfs_to_destination(rng, input, &DIR_LIST, start, b.steps_[k], &mut action_top, config.reroute_limit);
+            let (_, actions) = action_top.into_sorted_vec().into_iter().next()?;
+            for action in actions {
+                child.advance(input, action);
+            }
+        } else {
+            // cの次のマスにそのまま進める
+            child.advanceByOutput(input, &b.output_[ib..ib + 1]);
+        }
+        child.advanceByOutput(input, &b.output_[k..]);
+    }
+    extend_tail_with(rng, input, &mut child, config.tail_policy, config.tail_rollouts);
+    child.evaluateScore();
+    Some(child)
+}
+
+// トーナメント選択: tournament_size個ランダムに選んで得点が一番高い個体
+fn select<'a>(rng: &mut Pcg64Mcg, population: &'a [State], tournament_size: usize) -> &'a State {
+    (0..tournament_size.max(1)).map(|_| &population[rng.gen_range(0, population.len())]).max_by_key(|state| state.game_score_).unwrap()
+}
+
+/// 遺伝的アルゴリズムで解く
+/// 各世代で，トーナメントで選んだ2つの親を交叉して子を作り，確率で繋ぎ直しの突然変異を入れる
+/// 親と子を合わせて得点の高い順にpopulation_size個を次の世代に残す (同じ経路は1つだけ残す)
+/// (最良の得点, その出力) を返す
+pub fn solve(input: &Input, config: &GeneticConfig) -> (i32, Output) {
+    let start_time = get_time();
+    let population_size = config.population_size.max(1);
+    let annealing = AnnealingConfig { solution_size: population_size, ..config.annealing.clone() };
+    let mut rng = Pcg64Mcg::new(annealing.seed);
+
+    let mut population: Vec<State> = make_initial_states(&mut rng, input, &annealing).into_sorted_vec();
+    population.reverse();
+    population.truncate(population_size);
+    while population.len() < population_size {
+        let mut state = State::new(input, !0, input.s);
+        extend_tail_with(&mut rng, input, &mut state, annealing.tail_policy, annealing.tail_rollouts);
+        state.evaluateScore();
+        population.push(state);
+    }
+    let mut best_score = population[0].game_score_;
+    let mut best_output = population[0].output_.clone();
+
+    let mut generation = 0;
+    loop {
+        let t = match config.max_generations {
+            Some(n) => generation as f64 / n as f64,
+            None => (get_time() - start_time) / config.time_limit,
+        };
+        if t >= 1.0 {
+            break;
+        }
+        generation += 1;
+        let mut children = vec![];
+        for _ in 0..config.offspring {
+            if config.max_generations.is_none() && get_time() - start_time >= config.time_limit {
+                break;
+            }
+            let a = select(&mut rng, &population, config.tournament_size);
+            let b = select(&mut rng, &population, config.tournament_size);
+            // 交叉に失敗したら親をそのまま使う (突然変異だけ入る)
+            let mut child = crossover(&mut rng, input, &annealing, a, b).unwrap_or_else(|| a.clone());
+            if rng.gen_bool(config.mutation_rate) {
+                for _ in 0..config.mutation_steps {
+                    if !anneal_step(&mut rng, input, &annealing, &mut child, t, &mut best_score, &mut best_output) {
+                        break;
+                    }
+                }
+            }
+            if child.game_score_ > best_score {
+                best_score = child.game_score_;
+                best_output = child.output_.clone();
+            }
+            children.push(child);
+        }
+        // 親と子を合わせて上位を残す
+        population.append(&mut children);
+        population.sort_by_key(|state| std::cmp::Reverse(state.game_score_));
+        let mut outputs = HashSet::new();
+        population.retain(|state| outputs.insert(state.output_.clone()));
+        population.truncate(population_size);
+    }
+    (best_score, best_output)
+}
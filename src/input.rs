@@ -0,0 +1,253 @@
+use std::fmt;
+
+// 各マスの得点の範囲 (問題文より 0 <= p <= 99)
+pub const MIN_POINT: i32 = 0;
+pub const MAX_POINT: i32 = 99;
+
+/// 入力で与えられる情報をまとめた構造体
+/// s: 開始位置
+/// tiles: タイルの位置
+/// ps: 座標ごとの得点
+/// h, w: 盤面の大きさ (本番は50x50だが，デバッグ用の小さい盤面なども扱えるようにしている)
+/// M: タイルの種類数 (tile_count()で読む．Stateを作るたびに盤面を走査しないようにnewで1回だけ数える)
+#[derive(Clone, Debug)]
+pub struct Input {
+    pub s: (usize, usize),
+    pub tiles: Vec<Vec<usize>>,
+    pub ps: Vec<Vec<i32>>,
+    pub h: usize,
+    pub w: usize,
+    M: usize,
+}
+
+/// 入力のどこがおかしかったか
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// 行が足りない
+    MissingLine,
+    /// 数値として読めない
+    InvalidNumber(String),
+    /// 1行の個数が違う
+    WrongTokenCount { expected: usize, found: usize },
+    /// 余計な行がある，またはtilesとpsの行数が合わない
+    ExtraLine,
+    /// sが盤面の外
+    StartOutOfBounds,
+    /// 得点が範囲外
+    PointOutOfRange(i32),
+    /// タイル番号がマスの数以上 (隙間なく番号を振ったらありえない)
+    TileIdOutOfRange(usize),
+    /// タイル番号が0..Mを隙間なく使っていない (この番号が抜けている)
+    MissingTileId(usize),
+    /// 3マス以上あるタイル
+    TileTooLarge(usize),
+    /// 2マスが隣り合っていないタイル
+    TileNotAdjacent(usize),
+}
+
+/// 入力の解析エラー
+/// line, column: 最初に見つかった問題の位置 (どちらも1始まり)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::MissingLine => write!(f, "unexpected end of input"),
+            ParseErrorKind::InvalidNumber(token) => write!(f, "invalid number `{}`", token),
+            ParseErrorKind::WrongTokenCount { expected, found } => write!(f, "expected {} values, found {}", expected, found),
+            ParseErrorKind::ExtraLine => write!(f, "unexpected line (tiles and ps must have the same number of rows)"),
+            ParseErrorKind::StartOutOfBounds => write!(f, "start position is out of the board"),
+            ParseErrorKind::PointOutOfRange(p) => write!(f, "point {} is out of range {}..={}", p, MIN_POINT, MAX_POINT),
+            ParseErrorKind::TileIdOutOfRange(id) => write!(f, "tile id {} is larger than the number of cells", id),
+            ParseErrorKind::MissingTileId(id) => write!(f, "tile id {} is never used (tile ids must be 0..M-1)", id),
+            ParseErrorKind::TileTooLarge(id) => write!(f, "tile {} has more than 2 cells", id),
+            ParseErrorKind::TileNotAdjacent(id) => write!(f, "cells of tile {} are not adjacent", id),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// 1行分の数値と，それぞれが何文字目から始まっていたか
+struct Row<T> {
+    line: usize,
+    values: Vec<T>,
+    columns: Vec<usize>,
+}
+
+// 空行でない行を (行番号, [(列番号, 文字列)]) にする
+fn tokenize(src: &str) -> Vec<(usize, Vec<(usize, &str)>)> {
+    let mut lines = vec![];
+    for (i, line) in src.lines().enumerate() {
+        let mut tokens = vec![];
+        let mut start = None;
+        for (col, (byte, c)) in line.char_indices().enumerate() {
+            if c.is_whitespace() {
+                if let Some((col0, byte0)) = start.take() {
+                    tokens.push((col0 + 1, &line[byte0..byte]));
+                }
+            } else if start.is_none() {
+                start = Some((col, byte));
+            }
+        }
+        if let Some((col0, byte0)) = start {
+            tokens.push((col0 + 1, &line[byte0..]));
+        }
+        if !tokens.is_empty() {
+            lines.push((i + 1, tokens));
+        }
+    }
+    lines
+}
+
+fn parse_row<T: std::str::FromStr>(line: usize, tokens: &[(usize, &str)], expected: usize) -> Result<Row<T>, ParseError> {
+    if tokens.len() != expected {
+        // 足りないなら行末，多すぎるなら余計な最初の値の位置を指す
+        let column = match tokens.get(expected) {
+            Some(&(col, _)) => col,
+            None => tokens.last().map_or(1, |&(col, t)| col + t.chars().count()),
+        };
+        return Err(ParseError { line, column, kind: ParseErrorKind::WrongTokenCount { expected, found: tokens.len() } });
+    }
+    let mut values = vec![];
+    let mut columns = vec![];
+    for &(column, token) in tokens {
+        let value = token.parse().map_err(|_| ParseError { line, column, kind: ParseErrorKind::InvalidNumber(token.to_string()) })?;
+        values.push(value);
+        columns.push(column);
+    }
+    Ok(Row { line, values, columns })
+}
+
+// 得点が範囲内か
+fn check_points(point_rows: &[Row<i32>]) -> Result<(), ParseError> {
+    for row in point_rows {
+        for (&p, &column) in row.values.iter().zip(row.columns.iter()) {
+            if !(MIN_POINT..=MAX_POINT).contains(&p) {
+                return Err(ParseError { line: row.line, column, kind: ParseErrorKind::PointOutOfRange(p) });
+            }
+        }
+    }
+    Ok(())
+}
+
+// タイル番号が0..Mを隙間なく使っていて，各タイルが1マスか隣り合う2マスか
+fn check_tiles(tile_rows: &[Row<usize>], h: usize, w: usize) -> Result<(), ParseError> {
+    let tiles: Vec<&Vec<usize>> = tile_rows.iter().map(|row| &row.values).collect();
+    let at = |i: usize, j: usize, kind: ParseErrorKind| ParseError { line: tile_rows[i].line, column: tile_rows[i].columns[j], kind };
+    for i in 0..h {
+        for j in 0..w {
+            if tiles[i][j] >= h * w {
+                return Err(at(i, j, ParseErrorKind::TileIdOutOfRange(tiles[i][j])));
+            }
+        }
+    }
+    let M = tiles.iter().copied().flatten().max().unwrap() + 1;
+    // タイル番号は隙間なく使われていないといけない (seenの長さをMにしているため)
+    let mut used = vec![false; M];
+    for &id in tiles.iter().copied().flatten() {
+        used[id] = true;
+    }
+    let missing_id = used.iter().position(|&u| !u);
+    // タイルごとに属するマスを集める
+    let mut cells: Vec<Vec<(usize, usize)>> = vec![vec![]; M];
+    for i in 0..h {
+        for j in 0..w {
+            let id = tiles[i][j];
+            // 抜けている番号より大きい番号が最初に出てくる位置を指す
+            if let Some(missing_id) = missing_id.filter(|&missing_id| id > missing_id) {
+                return Err(at(i, j, ParseErrorKind::MissingTileId(missing_id)));
+            }
+            cells[id].push((i, j));
+            match cells[id].len() {
+                1 => {}
+                2 => {
+                    let (i0, j0) = cells[id][0];
+                    if i0.abs_diff(i) + j0.abs_diff(j) != 1 {
+                        return Err(at(i, j, ParseErrorKind::TileNotAdjacent(id)));
+                    }
+                }
+                _ => return Err(at(i, j, ParseErrorKind::TileTooLarge(id))),
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Input {
+    /// 盤面の大きさとタイルの種類数はtilesから決める
+    pub fn new(s: (usize, usize), tiles: Vec<Vec<usize>>, ps: Vec<Vec<i32>>) -> Self {
+        let h = tiles.len();
+        let w = tiles.first().map_or(0, |row| row.len());
+        let M = tiles.iter().flatten().max().map_or(0, |&id| id + 1);
+        Self { s, tiles, ps, h, w, M }
+    }
+
+    /// 問題の入力形式の文字列から読み，中身が正しいかも確かめる
+    /// 1行目がs，続くh行がtiles，続くh行がpsなので，行数と1行目のtilesの個数から盤面の大きさを推定する
+    /// 入力にhはないので，tilesとpsの行数がずれていても合計が偶数で中身の検証を通れば，別の形の盤面として読んでしまう
+    /// 形が揃っているか，タイル番号が0..Mを隙間なく使っているか，タイルが1マスか隣り合う2マスか，
+    /// sが盤面の中か，得点が範囲内かを見て，最初に見つかった問題の行と列を返す
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let lines = tokenize(src);
+        let last_line = src.lines().count().max(1);
+        let missing = |line: usize| ParseError { line, column: 1, kind: ParseErrorKind::MissingLine };
+
+        // 1行目: s
+        let (line, tokens) = lines.first().ok_or_else(|| missing(1))?;
+        let s_row = parse_row::<usize>(*line, tokens, 2)?;
+        let s = (s_row.values[0], s_row.values[1]);
+
+        // 残りの行の前半がtiles，後半がps
+        let body = &lines[1..];
+        if body.is_empty() {
+            return Err(missing(last_line + 1));
+        }
+        if body.len() % 2 == 1 {
+            let (line, _) = body[body.len() - 1];
+            return Err(ParseError { line, column: 1, kind: ParseErrorKind::ExtraLine });
+        }
+        let h = body.len() / 2;
+        let w = body[0].1.len();
+        let mut tile_rows = vec![];
+        for (line, tokens) in &body[..h] {
+            tile_rows.push(parse_row::<usize>(*line, tokens, w)?);
+        }
+        let mut point_rows = vec![];
+        for (line, tokens) in &body[h..] {
+            point_rows.push(parse_row::<i32>(*line, tokens, w)?);
+        }
+
+        // 中身の検証はそれぞれ独立なので全部見て，入力の中で一番前にある問題を返す
+        let mut errors = vec![];
+        if s.0 >= h || s.1 >= w {
+            let column = if s.0 >= h { s_row.columns[0] } else { s_row.columns[1] };
+            errors.push(ParseError { line: s_row.line, column, kind: ParseErrorKind::StartOutOfBounds });
+        }
+        errors.extend(check_tiles(&tile_rows, h, w).err());
+        errors.extend(check_points(&point_rows).err());
+        if let Some(e) = errors.into_iter().min_by_key(|e| (e.line, e.column)) {
+            return Err(e);
+        }
+
+        let tiles = tile_rows.into_iter().map(|row| row.values).collect();
+        let ps = point_rows.into_iter().map(|row| row.values).collect();
+        Ok(Self::new(s, tiles, ps))
+    }
+
+    /// parseのエラーをpanicにする版 (入力が正しいと分かっているとき用)
+    pub fn read(src: &str) -> Self {
+        Self::parse(src).unwrap_or_else(|e| panic!("invalid input: {}", e))
+    }
+
+    /// タイルの種類数M (seenの長さ)
+    pub fn tile_count(&self) -> usize {
+        self.M
+    }
+}
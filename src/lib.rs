@@ -0,0 +1,65 @@
+#![allow(non_snake_case)]
+// 盤面は添字で触ることが多いのでインデックスでのループを許す
+#![allow(clippy::needless_range_loop)]
+
+// src/bin 以下の提出用ファイルはAtCoderに出すため1ファイルで完結させているが，
+// 複数の解法から使い回したい部品はこちらに切り出していく
+
+pub mod annealing;
+pub mod best;
+pub mod budget;
+pub mod dfs;
+pub mod engine;
+pub mod exact;
+pub mod freespace;
+pub mod generator;
+pub mod genetic;
+pub mod input;
+pub mod lns;
+pub mod mcts;
+pub mod offline;
+pub mod region;
+pub mod safety;
+pub mod scorer;
+pub mod search;
+pub mod segment;
+pub mod state;
+pub mod tail;
+pub mod testcase;
+pub mod time;
+pub mod topk;
+
+pub use input::Input;
+pub use state::{State, TileState};
+pub use time::get_time;
+
+// 型の定義
+pub type Action = usize;
+pub type Actions = Vec<usize>;
+pub type ScoreType = i32;
+pub type Output = String;
+
+// 定数
+pub const INF: ScoreType = 1000000000;
+pub const DIJ: [(usize, usize); 4] = [(0, !0), (0, 1), (!0, 0), (1, 0)];
+pub const DIR: [char; 4] = ['L', 'R', 'U', 'D'];
+// DFSで方向を調べる順序をいくらか持つ
+// [左or右, 上or下, 左or右, 上or下], [上or下, 左or右, 上or下, 左or右]の計8通り
+pub const DIR_LIST: [[usize; 4]; 8] = [
+    [0,2,1,3], [0,3,1,2], [1,2,0,3], [1,3,0,2],
+    [2,0,3,1], [2,1,3,0], [3,0,2,1], [3,1,2,0]
+];
+
+/// LRUDの文字をactionに変換する
+pub fn char_to_action(c: char) -> Option<Action> {
+    DIR.iter().position(|&d| d == c)
+}
+
+/// pathにcontentsを書く
+/// 書いている途中で止められても壊れないように，一時ファイル(拡張子.tmp)に書いてから置き換える
+/// 失敗したら失敗したファイルのパスも返す
+pub fn write_atomic(path: &std::path::Path, contents: &str) -> Result<(), (std::path::PathBuf, std::io::Error)> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, contents).map_err(|e| (tmp_path.clone(), e))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| (path.to_path_buf(), e))
+}
@@ -0,0 +1,213 @@
+// 大近傍探索 (LNS)
+// 焼きなましの繋ぎ直しは経路の添字の区間p1..p2を壊してランダムDFSで直すだけなので，局所解から抜けにくい
+// ここでは盤面上の長方形を選び，その中を通る部分をまとめて壊してビームサーチで直す
+
+use crate::annealing::{self, make_initial_states, splice, AnnealingConfig};
+use crate::budget::Budget;
+use crate::tail::extend_tail_with;
+use crate::*;
+use std::collections::HashSet;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// LNSのパラメータ
+/// time_limit: 全体の制限時間(秒)
+/// max_iterations: 壊して直す回数の上限 (Someなら時間を見ずにこの回数だけ回すので結果が再現する)
+/// anneal_fraction: 最初にtime_limitのこの割合だけ焼きなましをしてからLNSに移る (0ならmake_initial_statesの一番よい初期解から始める)
+///   max_iterationsがSomeのときは時間ではなくannealing.max_iterationsで焼きなましを止める (time_limitはINFINITYにしておくこと)
+/// rect_min, rect_max: 壊す長方形の縦横の長さの範囲
+/// max_window: 壊す区間の手数の上限 (長方形を出入りして長くなりすぎた区間は壊さない)
+/// beam_width: 直すビームサーチのビーム幅
+/// annealing: 初期解の作り方・焼きなまし・お尻の伸ばし方に使う
+#[derive(Clone, Debug)]
+pub struct LnsConfig {
+    pub time_limit: f64,
+    pub max_iterations: Option<usize>,
+    pub anneal_fraction: f64,
+    pub rect_min: usize,
+    pub rect_max: usize,
+    pub max_window: usize,
+    pub beam_width: usize,
+    pub annealing: AnnealingConfig,
+}
+
+impl Default for LnsConfig {
+    fn default() -> Self {
+        Self {
+            time_limit: 1.9,
+            max_iterations: None,
+            anneal_fraction: 0.5,
+            rect_min: 3,
+            rect_max: 10,
+            max_window: 300,
+            beam_width: 16,
+            annealing: AnnealingConfig::default(),
+        }
+    }
+}
+
+// ビームサーチの1状態
+// 直す範囲のタイルに0から番号を振り，踏んだかどうかをbitsで持つ (盤面全体のseenをcloneしないため)
+#[derive(Clone)]
+struct BeamNode {
+    pos: (usize, usize),
+    bits: Vec<u64>,
+    actions: Actions,
+    score: i32,
+}
+
+impl BeamNode {
+    fn has(&self, local: usize) -> bool {
+        self.bits[local / 64] >> (local % 64) & 1 == 1
+    }
+
+    // 重複を除くためのキー (位置と踏んだタイルの集合)
+    fn key(&self) -> ((usize, usize), Vec<u64>) {
+        (self.pos, self.bits.clone())
+    }
+}
+
+/// p1からgoalまで，seenのタイルを踏まずに長方形bounds (imin, imax, jmin, jmax) の中だけを通る経路をビームサーチで探す
+/// goalがNoneなら終点は自由 (お尻を壊したとき)
+/// 見つかった経路のうち (p1の次からgoalまでの得点, 行動列) が一番よいものを返す
+pub fn beam_repair(input: &Input, seen: &[bool], p1: (usize, usize), goal: Option<(usize, usize)>, bounds: (usize, usize, usize, usize), beam_width: usize) -> Option<(i32, Actions)> {
+    let (imin, imax, jmin, jmax) = bounds;
+    // 範囲内の踏んでいないタイルに番号を振る
+    let mut local = vec![!0; seen.len()];
+    let mut free_count: usize = 0;
+    for i in imin..=imax {
+        for j in jmin..=jmax {
+            let tile = input.tiles[i][j];
+            if !seen[tile] && local[tile] == !0 {
+                local[tile] = free_count;
+                free_count += 1;
+            }
+        }
+    }
+    let mut beam = vec![BeamNode { pos: p1, bits: vec![0; free_count.div_ceil(64)], actions: vec![], score: 0 }];
+    let mut best: Option<(i32, Actions)> = None;
+    // 1手ごとに1つタイルを踏むので，free_count手より長い経路はない
+    for depth in 0..free_count {
+        let mut next_beam = vec![];
+        for node in &beam {
+            for (action, &(di, dj)) in DIJ.iter().enumerate() {
+                let ni = node.pos.0.wrapping_add(di);
+                let nj = node.pos.1.wrapping_add(dj);
+                if ni < imin || ni > imax || nj < jmin || nj > jmax {
+                    continue;
+                }
+                let tile = input.tiles[ni][nj];
+                if seen[tile] || node.has(local[tile]) {
+                    continue;
+                }
+                // 残りの手数でgoalに着けないなら捨てる
+                if let Some(goal) = goal {
+                    if ni.abs_diff(goal.0) + nj.abs_diff(goal.1) > free_count - depth - 1 {
+                        continue;
+                    }
+                }
+                let mut child = node.clone();
+                child.pos = (ni, nj);
+                child.bits[local[tile] / 64] |= 1 << (local[tile] % 64);
+                child.actions.push(action);
+                child.score += input.ps[ni][nj];
+                if goal.is_none_or(|goal| child.pos == goal) {
+                    if best.as_ref().is_none_or(|best| best.0 < child.score) {
+                        best = Some((child.score, child.actions.clone()));
+                    }
+                    // goalに着いたらそこで終わり
+                    if goal.is_some() {
+                        continue;
+                    }
+                }
+                next_beam.push(child);
+            }
+        }
+        if next_beam.is_empty() {
+            break;
+        }
+        next_beam.sort_by_key(|node| std::cmp::Reverse(node.score));
+        let mut keys = HashSet::new();
+        next_beam.retain(|node| keys.insert(node.key()));
+        next_beam.truncate(beam_width.max(1));
+        beam = next_beam;
+    }
+    best
+}
+
+/// 長方形を1つ壊して直す
+/// 長方形に入る直前のマスp1から，最後に出た直後のマスp2までの区間を全部壊し，
+/// 長方形と壊した区間を囲む範囲の中でビームサーチで繋ぎ直す (p2がなければお尻を作り直す)
+/// 得点が下がらなければcrt_stateを書き換えてtrueを返す
+pub fn destroy_and_repair(rng: &mut Pcg64Mcg, input: &Input, config: &LnsConfig, crt_state: &mut State) -> bool {
+    let steps = &crt_state.steps_;
+    let (ci, cj) = steps[rng.gen_range(0, steps.len())];
+    let rect_min = config.rect_min.max(1);
+    let rh = rng.gen_range(rect_min, config.rect_max.max(rect_min) + 1);
+    let rw = rng.gen_range(rect_min, config.rect_max.max(rect_min) + 1);
+    let (ri, rj) = (ci.saturating_sub(rh / 2), cj.saturating_sub(rw / 2));
+    let (ri2, rj2) = ((ri + rh - 1).min(input.h - 1), (rj + rw - 1).min(input.w - 1));
+    let inside = |&(i, j): &(usize, usize)| ri <= i && i <= ri2 && rj <= j && j <= rj2;
+    let first = steps.iter().position(inside).unwrap();
+    let last = steps.iter().rposition(inside).unwrap();
+    // sは動かせないので，sが長方形の中ならsから壊す
+    let p1_idx = first.saturating_sub(1);
+    let p2_idx = last + 1;
+    if p2_idx - p1_idx > config.max_window {
+        return false;
+    }
+    let goal = steps.get(p2_idx).copied();
+
+    // 区間のタイルを空ける (p1は残す)
+    let mut seen = crt_state.seen_.clone();
+    let (mut imin, mut imax, mut jmin, mut jmax) = (ri, ri2, rj, rj2);
+    for &(i, j) in &steps[p1_idx..(p2_idx + 1).min(steps.len())] {
+        imin = imin.min(i);
+        imax = imax.max(i);
+        jmin = jmin.min(j);
+        jmax = jmax.max(j);
+    }
+    for &(i, j) in &steps[p1_idx + 1..(p2_idx + 1).min(steps.len())] {
+        seen[input.tiles[i][j]] = false;
+    }
+    seen[input.tiles[steps[p1_idx].0][steps[p1_idx].1]] = true;
+
+    let (_, actions) = match beam_repair(input, &seen, steps[p1_idx], goal, (imin, imax, jmin, jmax), config.beam_width) {
+        Some(repaired) => repaired,
+        None => return false,
+    };
+    let mut next_state = splice(input, &crt_state.output_, p1_idx, &actions, p2_idx.min(steps.len() - 1));
+    extend_tail_with(rng, input, &mut next_state, config.annealing.tail_policy, config.annealing.tail_rollouts);
+    next_state.evaluateScore();
+    if next_state.game_score_ >= crt_state.game_score_ {
+        *crt_state = next_state;
+        true
+    } else {
+        false
+    }
+}
+
+/// (焼きなましをしてから) LNSで解く
+/// (最良の得点, その出力) を返す
+pub fn solve(input: &Input, config: &LnsConfig) -> (i32, Output) {
+    let mut budget = Budget::new(config.time_limit, &[("anneal", config.anneal_fraction), ("lns", 1.0 - config.anneal_fraction)]);
+    let mut rng = Pcg64Mcg::new(config.annealing.seed);
+    let mut crt_state = if config.anneal_fraction > 0.0 {
+        // max_iterationsがSomeならtime_limitはINFINITYなので，焼きなましもannealing.max_iterationsで止まる
+        let time_limit = budget.begin("anneal") - get_time();
+        let annealing_config = AnnealingConfig { time_limit, ..config.annealing.clone() };
+        let (_, output) = annealing::solve(input, &annealing_config);
+        State::fromOutput(input, &output).unwrap()
+    } else {
+        make_initial_states(&mut rng, input, &config.annealing).pop().unwrap()
+    };
+    // 焼きなましが早く終わった分もLNSに回る
+    budget.begin("lns");
+    let mut iter = 0;
+    while config.max_iterations.is_none_or(|n| iter < n) && (config.max_iterations.is_some() || !budget.phase_over()) {
+        iter += 1;
+        destroy_and_repair(&mut rng, input, config, &mut crt_state);
+    }
+    (crt_state.game_score_, crt_state.output_)
+}
@@ -0,0 +1,312 @@
+// モンテカルロ木探索 (UCT)
+// SearchStateを実装していれば何にでも使える．1手ずつ木を作り直して行動を決め，経路を伸ばしていく
+
+use crate::search::{greedyAction, randomAction, SearchState};
+use crate::*;
+
+use rand_pcg::Pcg64Mcg;
+
+/// プレイアウトで次の手をどう選ぶか
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RolloutPolicy {
+    Random,
+    Greedy,
+}
+
+/// MCTSのパラメータ
+/// time_limit: 経路全体を作るのにかける時間(秒)
+/// playouts_per_action: 1手決めるのに回すプレイアウトの回数 (Someなら時間を見ずにこの回数だけ回すので結果が再現する)
+/// time_per_action: Someなら1手決めるのにかける時間(秒)をこれに固定する (playouts_per_actionがNoneのとき)
+///   Noneなら残り時間を残りの手数の見積もりで割って決める
+/// exploration: UCB1の探索項の係数 (報酬はそれまでに見た最小〜最大で0~1に正規化する)
+/// expand_threshold: 葉をこの回数訪れたら子を展開する
+/// rollout: プレイアウトの方策, rollout_depth: プレイアウトで進める手数の上限 (Noneなら終わるまで)
+/// seed: 乱数のシード
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    pub time_limit: f64,
+    pub playouts_per_action: Option<usize>,
+    pub time_per_action: Option<f64>,
+    pub exploration: f64,
+    pub expand_threshold: usize,
+    pub rollout: RolloutPolicy,
+    pub rollout_depth: Option<usize>,
+    pub seed: u128,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            time_limit: 1.9,
+            playouts_per_action: None,
+            time_per_action: None,
+            exploration: 0.7,
+            expand_threshold: 3,
+            rollout: RolloutPolicy::Random,
+            rollout_depth: Some(200),
+            seed: 20221210,
+        }
+    }
+}
+
+/// 1手決めたときの木の統計
+/// playouts: 回したプレイアウトの数, nodes: 作った木のノード数, max_depth: 木の深さ
+/// root_children: ルートの子ごとの (行動, 訪問回数, 報酬の平均)
+#[derive(Clone, Debug, Default)]
+pub struct MctsStats {
+    pub playouts: usize,
+    pub nodes: usize,
+    pub max_depth: usize,
+    pub root_children: Vec<(Action, usize, f64)>,
+}
+
+/// 経路全体を作ったときの統計
+/// steps: MCTSで決めた手数 (時間切れの後に方策だけで伸ばした分は含まない)
+/// playouts, nodes: 全手の合計, max_depth: 全手での最大
+/// root_visits: 各手で選んだ子の訪問回数 (どれくらい自信をもって決めたか)
+#[derive(Clone, Debug, Default)]
+pub struct MctsReport {
+    pub steps: usize,
+    pub playouts: usize,
+    pub nodes: usize,
+    pub max_depth: usize,
+    pub root_visits: Vec<usize>,
+}
+
+// 木のノード (子は展開するまで空)
+struct MctsNode {
+    visits: usize,
+    total: f64,
+    children: Vec<(Action, usize)>,
+    expanded: bool,
+}
+
+impl MctsNode {
+    fn new() -> Self {
+        Self { visits: 0, total: 0.0, children: vec![], expanded: false }
+    }
+}
+
+// 1手分の木 (ノードは配列に置いて添字で持つ)
+struct Tree<'a, S: SearchState> {
+    input: &'a S::Input,
+    config: &'a MctsConfig,
+    nodes: Vec<MctsNode>,
+    // 報酬の正規化に使う，これまでに見た報酬の範囲
+    lo: f64,
+    hi: f64,
+    max_depth: usize,
+}
+
+impl<S: SearchState> Tree<'_, S> {
+    fn normalize(&self, reward: f64) -> f64 {
+        if self.hi > self.lo {
+            (reward - self.lo) / (self.hi - self.lo)
+        } else {
+            0.5
+        }
+    }
+
+    // UCB1が最大の子 (まだ訪れていない子があればそれを優先)
+    fn select_child(&self, node: usize) -> (Action, usize) {
+        let parent = &self.nodes[node];
+        let log_n = (parent.visits.max(1) as f64).ln();
+        let mut best = parent.children[0];
+        let mut best_ucb = f64::NEG_INFINITY;
+        for &(action, child) in &parent.children {
+            let c = &self.nodes[child];
+            if c.visits == 0 {
+                return (action, child);
+            }
+            let ucb = self.normalize(c.total / c.visits as f64) + self.config.exploration * (log_n / c.visits as f64).sqrt();
+            if ucb > best_ucb {
+                best_ucb = ucb;
+                best = (action, child);
+            }
+        }
+        best
+    }
+
+    // ルートから1回分: 選択 -> 展開 -> プレイアウト -> 逆伝播
+    fn iterate(&mut self, rng: &mut Pcg64Mcg, root_state: &S) {
+        let mut state = root_state.clone();
+        let mut path = vec![0];
+        let mut node = 0;
+        loop {
+            if !self.nodes[node].expanded {
+                // 十分訪れた葉は子を展開する (展開した子の1つからプレイアウトする)
+                if self.nodes[node].visits < self.config.expand_threshold || state.is_done() {
+                    break;
+                }
+                self.nodes[node].expanded = true;
+                for action in state.legal_actions(self.input) {
+                    self.nodes.push(MctsNode::new());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push((action, child));
+                }
+            }
+            if self.nodes[node].children.is_empty() {
+                break;
+            }
+            let (action, child) = self.select_child(node);
+            state.advance(self.input, action);
+            node = child;
+            path.push(node);
+        }
+        self.max_depth = self.max_depth.max(path.len() - 1);
+
+        let reward = rollout(rng, self.input, self.config, state) as f64;
+        self.lo = self.lo.min(reward);
+        self.hi = self.hi.max(reward);
+        for &node in &path {
+            self.nodes[node].visits += 1;
+            self.nodes[node].total += reward;
+        }
+    }
+}
+
+// 方策に従って次の1手を選ぶ
+fn policy_action<S: SearchState>(rng: &mut Pcg64Mcg, input: &S::Input, policy: RolloutPolicy, state: &S) -> Option<Action> {
+    match policy {
+        RolloutPolicy::Random => randomAction(rng, input, state),
+        RolloutPolicy::Greedy => greedyAction(input, state),
+    }
+}
+
+// 方策に従って終わるまで(かrollout_depth手)進め，評価値を返す
+fn rollout<S: SearchState>(rng: &mut Pcg64Mcg, input: &S::Input, config: &MctsConfig, mut state: S) -> ScoreType {
+    let mut depth = 0;
+    while !state.is_done() && config.rollout_depth.is_none_or(|max_depth| depth < max_depth) {
+        match policy_action(rng, input, config.rollout, &state) {
+            Some(action) => state.advance(input, action),
+            None => break,
+        }
+        depth += 1;
+    }
+    state.evaluate()
+}
+
+/// stateからMCTSで次の1手を決める
+/// 打てる手がなければNone，あれば (一番訪問回数の多い子の行動, 統計)
+/// time_limit: この1手にかけてよい時間(秒) (config.playouts_per_actionがSomeなら使わない)
+pub fn mctsAction<S: SearchState>(rng: &mut Pcg64Mcg, input: &S::Input, state: &S, config: &MctsConfig, time_limit: f64) -> Option<(Action, MctsStats)> {
+    let start_time = get_time();
+    let mut root = MctsNode::new();
+    root.expanded = true;
+    let mut tree = Tree { input, config, nodes: vec![root], lo: f64::INFINITY, hi: f64::NEG_INFINITY, max_depth: 0 };
+    for action in state.legal_actions(input) {
+        tree.nodes.push(MctsNode::new());
+        let child = tree.nodes.len() - 1;
+        tree.nodes[0].children.push((action, child));
+    }
+    if tree.nodes[0].children.is_empty() {
+        return None;
+    }
+    // 1つしか打てないなら考えるまでもない
+    if tree.nodes[0].children.len() > 1 {
+        let mut playouts = 0;
+        loop {
+            // 時間で打ち切るときも1回は回す
+            let over = match config.playouts_per_action {
+                Some(n) => playouts >= n,
+                None => playouts > 0 && get_time() - start_time >= time_limit,
+            };
+            if over {
+                break;
+            }
+            tree.iterate(rng, state);
+            playouts += 1;
+        }
+    }
+
+    let root_children: Vec<(Action, usize, f64)> = tree.nodes[0]
+        .children
+        .iter()
+        .map(|&(action, child)| {
+            let c = &tree.nodes[child];
+            (action, c.visits, if c.visits == 0 { 0.0 } else { c.total / c.visits as f64 })
+        })
+        .collect();
+    // 訪問回数が最大の子を選ぶ (同じなら平均報酬の高い方)
+    let &(best_action, _, _) = root_children.iter().max_by(|a, b| (a.1, a.2).partial_cmp(&(b.1, b.2)).unwrap()).unwrap();
+    let stats = MctsStats { playouts: tree.nodes[0].visits, nodes: tree.nodes.len(), max_depth: tree.max_depth, root_children };
+    Some((best_action, stats))
+}
+
+// 残りの手数を見積もるのに回すプレイアウトの数
+const ESTIMATE_PLAYOUTS: usize = 8;
+
+// stateからプレイアウトの方策で終わるまで進めたときの手数 (ESTIMATE_PLAYOUTS回の最大)
+// MCTSで選ぶ手の方が長く続くので，平均ではなく最大を見積もりにする
+fn estimate_remaining_steps<S: SearchState>(rng: &mut Pcg64Mcg, input: &S::Input, policy: RolloutPolicy, state: &S) -> usize {
+    (0..ESTIMATE_PLAYOUTS)
+        .map(|_| {
+            let mut state = state.clone();
+            let mut steps = 0;
+            while !state.is_done() {
+                match policy_action(rng, input, policy, &state) {
+                    Some(action) => state.advance(input, action),
+                    None => break,
+                }
+                steps += 1;
+            }
+            steps
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// stateから1手ずつMCTSで決めて進め，打てる手がなくなったら終わる
+/// 1手にかける時間は，残り時間をプレイアウトの方策で見積もった残りの手数で割ったもの (config.time_per_actionがSomeならそれ)
+/// 見積もりは方策のプレイアウトより長く続くMCTSの経路に対しては少なめになるので，毎手見積もり直す
+/// (そうすると使う時間は手が進むにつれて少しずつ減り，経路が見積もりより長くても時間が残る)
+/// 全体の時間(config.time_limit)を使い切ったら，残りはプレイアウトの方策だけで伸ばす
+/// (最後の状態, 統計) を返す
+pub fn solve<S: SearchState>(input: &S::Input, mut state: S, config: &MctsConfig) -> (S, MctsReport) {
+    let start_time = get_time();
+    let mut rng = Pcg64Mcg::new(config.seed);
+    let mut report = MctsReport::default();
+    while !state.is_done() {
+        let remain = config.time_limit - (get_time() - start_time);
+        let time_per_action = match config.time_per_action {
+            Some(t) => t.min(remain),
+            None if config.playouts_per_action.is_none() && remain > 0.0 => remain / estimate_remaining_steps(&mut rng, input, config.rollout, &state).max(1) as f64,
+            None => remain,
+        };
+        let action = if config.playouts_per_action.is_some() || remain > 0.0 {
+            mctsAction(&mut rng, input, &state, config, time_per_action).map(|(action, stats)| {
+                report.steps += 1;
+                report.playouts += stats.playouts;
+                report.nodes += stats.nodes;
+                report.max_depth = report.max_depth.max(stats.max_depth);
+                report.root_visits.push(stats.root_children.iter().find(|c| c.0 == action).map_or(0, |c| c.1));
+                action
+            })
+        } else {
+            policy_action(&mut rng, input, config.rollout, &state)
+        };
+        match action {
+            Some(action) => state.advance(input, action),
+            None => break,
+        }
+    }
+    (state, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+
+    #[test]
+    fn solve_spreads_the_time_over_the_path() {
+        let input = generate(0, 20, 20);
+        let config = MctsConfig { time_limit: 0.3, ..Default::default() };
+        let start = get_time();
+        let (state, report) = solve(&input, State::new(&input, !0, input.s), &config);
+        let elapsed = get_time() - start;
+        // 時間を余らせず，最後の方まで(方策だけで伸ばさずに)MCTSで決める
+        assert!((0.2..0.4).contains(&elapsed), "{:.3}s", elapsed);
+        assert!(report.steps * 10 >= state.output_.len() * 9, "{} / {}", report.steps, state.output_.len());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::annealing::{anneal_step, make_initial_states, AnnealingConfig};
+use crate::state::ReplayError;
+use crate::*;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// 時間をかけて1つの盤面を焼き続けるモードの設定
+/// annealing: 焼きなましのパラメータ (time_limitとmax_iterationsは使わない)
+/// round_iterations: 温度をt0からt1まで下げる1周分の反復回数 (1周終わるたびにbestから焼き直す)
+/// checkpoint_path: 途中経過を書き出すファイル (あればそこから再開する)
+/// checkpoint_interval: 途中経過を書き出す間隔(秒)
+/// target_score: この得点以上になったら止める
+/// wall_limit: この秒数だけ動いたら止める (再開した場合はその実行の開始から数える)
+#[derive(Clone, Debug)]
+pub struct OfflineConfig {
+    pub annealing: AnnealingConfig,
+    pub round_iterations: usize,
+    pub checkpoint_path: PathBuf,
+    pub checkpoint_interval: f64,
+    pub target_score: Option<i32>,
+    pub wall_limit: Option<f64>,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            annealing: AnnealingConfig::default(),
+            round_iterations: 1_000_000,
+            checkpoint_path: PathBuf::from("checkpoint.txt"),
+            checkpoint_interval: 60.0,
+            target_score: None,
+            wall_limit: None,
+        }
+    }
+}
+
+/// 途中経過
+/// seed: 続きで使う乱数のシード (書き出すたびに乱数から引き直して差し替えるので，再開しても同じ乱数列になる)
+/// round, iter: 何周目の何反復目まで終わったか
+/// current: 今焼いている経路, best: これまでで最良の経路
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub seed: u128,
+    pub round: usize,
+    pub iter: usize,
+    pub best_score: i32,
+    pub best: Output,
+    pub current: Output,
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// ファイルが読み書きできない
+    Io(PathBuf, std::io::Error),
+    /// ファイルの中身が読めない (行番号は1始まり)
+    Format(PathBuf, usize, String),
+    /// 経路がこの盤面では再生できない (別の盤面のチェックポイントなど)
+    Replay(PathBuf, ReplayError),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            CheckpointError::Format(path, line, msg) => write!(f, "{}:{}: {}", path.display(), line, msg),
+            CheckpointError::Replay(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl Checkpoint {
+    /// 「キー 値」の行を並べたテキストにする
+    pub fn to_text(&self) -> String {
+        format!(
+            "seed {}\nround {}\niter {}\nbest_score {}\nbest {}\ncurrent {}\n",
+            self.seed, self.round, self.iter, self.best_score, self.best, self.current
+        )
+    }
+
+    pub fn from_text(path: &Path, src: &str) -> Result<Self, CheckpointError> {
+        let error = |line: usize, msg: String| CheckpointError::Format(path.to_path_buf(), line, msg);
+        let mut values = std::collections::HashMap::new();
+        for (i, line) in src.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            values.insert(key, (i + 1, value.trim()));
+        }
+        let last_line = src.lines().count() + 1;
+        let get = |key: &str| values.get(key).copied().ok_or_else(|| error(last_line, format!("missing `{}`", key)));
+        macro_rules! number {
+            ($key:expr) => {{
+                let (line, value) = get($key)?;
+                value.parse().map_err(|_| error(line, format!("invalid {} `{}`", $key, value)))?
+            }};
+        }
+        Ok(Self {
+            seed: number!("seed"),
+            round: number!("round"),
+            iter: number!("iter"),
+            best_score: number!("best_score"),
+            best: get("best")?.1.to_string(),
+            current: get("current")?.1.to_string(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CheckpointError> {
+        let src = std::fs::read_to_string(path).map_err(|e| CheckpointError::Io(path.to_path_buf(), e))?;
+        Self::from_text(path, &src)
+    }
+
+    /// 途中で止められても壊れないようにwrite_atomicで書く
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        write_atomic(path, &self.to_text()).map_err(|(path, e)| CheckpointError::Io(path, e))
+    }
+}
+
+/// 制限時間を気にせず焼きなましを回し続け，checkpoint_interval秒ごとに途中経過を書き出す
+/// checkpoint_pathにファイルがあればその続きから始める
+/// target_scoreに届くかwall_limit秒たったら，途中経過を書き出して (最良スコア, 最良の出力) を返す
+/// どちらも指定しなければ止まらないので，Ctrl-Cで止めて再開する使い方になる
+pub fn run(input: &Input, config: &OfflineConfig) -> Result<(i32, Output), CheckpointError> {
+    let wall_start = Instant::now();
+    let path = config.checkpoint_path.as_path();
+    let mut checkpoint = if path.exists() {
+        Checkpoint::load(path)?
+    } else {
+        // 初回はいつもの初期解から始める
+        let mut rng = Pcg64Mcg::new(config.annealing.seed);
+        let state_bh = make_initial_states(&mut rng, input, &config.annealing);
+        let state = state_bh.peek().cloned().unwrap_or_else(|| State::new(input, !0, input.s));
+        Checkpoint {
+            seed: rng.gen(),
+            round: 0,
+            iter: 0,
+            best_score: state.game_score_,
+            best: state.output_.clone(),
+            current: state.output_,
+        }
+    };
+    let replay = |output: &str| State::fromOutput(input, output).map_err(|e| CheckpointError::Replay(path.to_path_buf(), e));
+    let mut crt_state = replay(&checkpoint.current)?;
+    // bestも再生して得点を確かめておく (書き換えられたファイルを信用しない)
+    checkpoint.best_score = replay(&checkpoint.best)?.game_score_;
+    let mut rng = Pcg64Mcg::new(checkpoint.seed);
+    let mut last_save = 0.0;
+
+    loop {
+        let elapsed = wall_start.elapsed().as_secs_f64();
+        let reached = config.target_score.is_some_and(|target| checkpoint.best_score >= target);
+        let timeout = config.wall_limit.is_some_and(|limit| elapsed >= limit);
+        if reached || timeout || elapsed - last_save >= config.checkpoint_interval {
+            // 乱数の状態はシードを引き直して差し替えることで保存する
+            checkpoint.seed = rng.gen();
+            rng = Pcg64Mcg::new(checkpoint.seed);
+            checkpoint.current = crt_state.output_.clone();
+            checkpoint.save(path)?;
+            last_save = elapsed;
+            if reached || timeout {
+                return Ok((checkpoint.best_score, checkpoint.best));
+            }
+        }
+        // 時間を見るのはたまにでいい
+        for _ in 0..100 {
+            checkpoint.iter += 1;
+            let t = checkpoint.iter as f64 / config.round_iterations as f64;
+            let movable = anneal_step(&mut rng, input, &config.annealing, &mut crt_state, t, &mut checkpoint.best_score, &mut checkpoint.best);
+            // 2点を選べないほど短い経路しかない盤面ならこれ以上やることはない
+            if !movable {
+                checkpoint.current = crt_state.output_.clone();
+                checkpoint.save(path)?;
+                return Ok((checkpoint.best_score, checkpoint.best));
+            }
+            // お尻を伸ばした分でbestを超えることもある
+            if crt_state.game_score_ > checkpoint.best_score {
+                checkpoint.best_score = crt_state.game_score_;
+                checkpoint.best = crt_state.output_.clone();
+            }
+            // 1周終わったらbestから焼き直す
+            if checkpoint.iter >= config.round_iterations {
+                checkpoint.round += 1;
+                checkpoint.iter = 0;
+                crt_state = replay(&checkpoint.best)?;
+                break;
+            }
+        }
+    }
+}
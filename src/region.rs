@@ -0,0 +1,177 @@
+use crate::engine::*;
+use crate::*;
+use std::collections::{HashMap, VecDeque};
+
+// ブロックの中を塗るDFSのフック
+// 方向dの順に見て，終点ごとに一番得点の高い経路を残す (次のブロックへ抜けられる終点を後で選ぶため)
+struct BlockHooks<'a> {
+    d: [usize; 4],
+    best_by_end: &'a mut HashMap<(usize, usize), (i32, Actions)>,
+}
+
+impl DfsHooks<PathState> for BlockHooks<'_> {
+    fn evaluate(&mut self, _input: &Input, state: &PathState) {
+        if self.best_by_end.get(&state.pos).is_none_or(|best| best.0 < state.score) {
+            self.best_by_end.insert(state.pos, (state.score, state.actions.clone()));
+        }
+    }
+
+    fn order(&mut self, _input: &Input, _state: &PathState, actions: &mut Actions) {
+        actions.sort_by_key(|a| self.d.iter().position(|x| x == a));
+    }
+}
+
+// ブロックの中を塗った後に，この個数先までのブロックのどれかへ抜けられればよいとする
+const BLOCK_LOOKAHEAD: usize = 3;
+
+/// 盤面をblock_size四方のブロックに分け，ブロック単位で回る順番を決めてから経路を作る
+/// sから1本のDFSで伸ばすだけだと遠くの得点の高い領域を狙えないので，その代わりの初期解として使う
+/// (1) sのあるブロックから始めて，まだ回っていないブロックのうち一番近い(同じなら得点の高い)ものへ移る順番を決める
+/// (2) ブロックの中は外に出ないDFSで蛇腹状に塗る (DIR_LISTの8通りの順でやって，次のブロックへ抜けられる経路のうち得点の一番高いものを採る)
+/// (3) 次のブロックへは踏んでいないタイルだけを通る最短路で移る (行けなければそのブロックは飛ばす)
+/// limit: ブロック1つ・方向の順1つあたりのDFSの打ち切り条件
+pub fn region_initial_state(input: &Input, block_size: usize, limit: DfsLimit) -> State {
+    let block_size = block_size.max(1);
+    let bh = input.h.div_ceil(block_size);
+    let bw = input.w.div_ceil(block_size);
+    let block_of = |(i, j): (usize, usize)| (i / block_size, j / block_size);
+
+    // ブロックごとの得点 (タイルごとに高い方のマスの得点を数える)
+    let M = input.tile_count();
+    let mut tile_best = vec![0; M];
+    for i in 0..input.h {
+        for j in 0..input.w {
+            let tile = input.tiles[i][j];
+            tile_best[tile] = tile_best[tile].max(input.ps[i][j]);
+        }
+    }
+    let mut block_value = vec![vec![0; bw]; bh];
+    let mut counted = vec![false; M];
+    for i in 0..input.h {
+        for j in 0..input.w {
+            let tile = input.tiles[i][j];
+            if !counted[tile] {
+                counted[tile] = true;
+                block_value[i / block_size][j / block_size] += tile_best[tile];
+            }
+        }
+    }
+
+    // ブロックを回る順番
+    let mut tour = vec![block_of(input.s)];
+    let mut visited = vec![vec![false; bw]; bh];
+    visited[input.s.0 / block_size][input.s.1 / block_size] = true;
+    for _ in 1..bh * bw {
+        let (ci, cj) = *tour.last().unwrap();
+        let mut next = None;
+        for bi in 0..bh {
+            for bj in 0..bw {
+                if visited[bi][bj] {
+                    continue;
+                }
+                let key = (ci.abs_diff(bi) + cj.abs_diff(bj), -block_value[bi][bj]);
+                if next.is_none_or(|(best_key, _)| key < best_key) {
+                    next = Some((key, (bi, bj)));
+                }
+            }
+        }
+        let (_, (bi, bj)) = next.unwrap();
+        visited[bi][bj] = true;
+        tour.push((bi, bj));
+    }
+
+    let mut state = State::new(input, !0, input.s);
+    for (k, &block) in tour.iter().enumerate() {
+        // ブロックの外にいるなら，最短路でブロックに入る
+        if block_of((state.pos_.i_, state.pos_.j_)) != block {
+            match shortest_path_to_block(input, &state, block_size, block) {
+                Some(actions) => {
+                    for action in actions {
+                        state.advance(input, action);
+                    }
+                }
+                None => continue,
+            }
+        }
+        // ブロックの中を塗る (ブロックの中にマスが1つもないタイルは踏んだことにしておく)
+        let mut seen = state.seen_.clone();
+        let mut inside = vec![false; M];
+        for i in block.0 * block_size..((block.0 + 1) * block_size).min(input.h) {
+            for j in block.1 * block_size..((block.1 + 1) * block_size).min(input.w) {
+                inside[input.tiles[i][j]] = true;
+            }
+        }
+        for tile in 0..M {
+            seen[tile] |= !inside[tile];
+        }
+        let pos = (state.pos_.i_, state.pos_.j_);
+        let mut best_by_end = HashMap::new();
+        for d in DIR_LIST {
+            let mut hooks = BlockHooks { d, best_by_end: &mut best_by_end };
+            DfsEngine::new(PathState::new(input, pos, seen.clone(), 0)).run(input, &mut hooks, limit);
+        }
+        // 得点の高い順に，まだ回っていないブロックのどれかへ抜けられる終点を探す (どこへも抜けられなければ一番得点の高いもの)
+        let mut candidates: Vec<(i32, Actions)> = best_by_end.into_values().collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        let mut chosen = None;
+        for (_, actions) in &candidates {
+            let mut next_state = state.clone();
+            for &action in actions {
+                next_state.advance(input, action);
+            }
+            let can_leave = tour[k + 1..].iter().take(BLOCK_LOOKAHEAD).any(|&next_block| shortest_path_to_block(input, &next_state, block_size, next_block).is_some());
+            if can_leave || k + 1 == tour.len() {
+                chosen = Some(next_state);
+                break;
+            }
+        }
+        state = match chosen {
+            Some(next_state) => next_state,
+            None => {
+                let mut next_state = state;
+                for &action in &candidates[0].1 {
+                    next_state.advance(input, action);
+                }
+                next_state
+            }
+        };
+    }
+    state.evaluateScore();
+    state
+}
+
+// stateの現在位置からブロックblockのどこかのマスまで，踏んでいないタイルだけを通る最短路
+// 訪問済みはマスではなくタイルで管理する (同じタイルの2マスを両方通る経路を作らないように)
+fn shortest_path_to_block(input: &Input, state: &State, block_size: usize, block: (usize, usize)) -> Option<Actions> {
+    let start = (state.pos_.i_, state.pos_.j_);
+    let mut prev = vec![vec![None; input.w]; input.h];
+    let mut reached = state.seen_.clone();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some((i, j)) = queue.pop_front() {
+        if (i / block_size, j / block_size) == block {
+            // 来た道を逆にたどる
+            let mut actions = vec![];
+            let (mut ci, mut cj) = (i, j);
+            while (ci, cj) != start {
+                let action: Action = prev[ci][cj].unwrap();
+                actions.push(action);
+                ci = ci.wrapping_sub(DIJ[action].0);
+                cj = cj.wrapping_sub(DIJ[action].1);
+            }
+            actions.reverse();
+            return Some(actions);
+        }
+        for (action, &(di, dj)) in DIJ.iter().enumerate() {
+            let ni = i.wrapping_add(di);
+            let nj = j.wrapping_add(dj);
+            if ni >= input.h || nj >= input.w || reached[input.tiles[ni][nj]] {
+                continue;
+            }
+            reached[input.tiles[ni][nj]] = true;
+            prev[ni][nj] = Some(action);
+            queue.push_back((ni, nj));
+        }
+    }
+    None
+}
@@ -0,0 +1,95 @@
+// どんなことがあっても何か正しい解を出力するための仕組み
+// 解法がpanicしたり，時間内に返ってこなかったりしても，それまでに見つけた最良の(検証済みの)経路を出力する
+
+use crate::scorer::compute_score;
+use crate::*;
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+/// これまでに見つけた最良の経路を覚えておく入れ物
+/// cloneしたものは中身を共有するので，解法・見張りのスレッド・mainで同じものを持つ
+/// offerで渡された経路はscorerで検証してから覚えるので，bestは常に正しい経路
+#[derive(Clone, Debug)]
+pub struct SafetyNet {
+    input: Arc<Input>,
+    best: Arc<Mutex<(i32, Output)>>,
+    // 出力したか．出力している間はロックを持ったままにする (負けた側は書き終わるまで待つ)
+    emitted: Arc<Mutex<bool>>,
+}
+
+impl SafetyNet {
+    /// 最初はsから動かない経路(空の出力)を覚えておく
+    pub fn new(input: &Input) -> Self {
+        let score = input.ps[input.s.0][input.s.1];
+        Self { input: Arc::new(input.clone()), best: Arc::new(Mutex::new((score, String::new()))), emitted: Arc::new(Mutex::new(false)) }
+    }
+
+    /// outputが正しい経路で，覚えているものより得点が高ければ覚えてtrueを返す
+    pub fn offer(&self, output: &str) -> bool {
+        let score = match compute_score(&self.input, output) {
+            Ok(score) => score,
+            Err(_) => return false,
+        };
+        // 解法のスレッドがpanicしてMutexが汚れていても中身は壊れていない (代入は1回でやっている)
+        let mut best = self.best.lock().unwrap_or_else(|e| e.into_inner());
+        if score <= best.0 {
+            return false;
+        }
+        *best = (score, output.trim().to_string());
+        true
+    }
+
+    /// 覚えている最良の (得点, 出力)
+    pub fn best(&self) -> (i32, Output) {
+        self.best.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 最良の経路を標準出力に出す (何回呼んでも，どのスレッドから呼んでも1回だけ出力する)
+    /// 出力したらtrue
+    pub fn emit(&self) -> bool {
+        self.emit_to(&mut std::io::stdout())
+    }
+
+    /// outに出す版
+    /// 別のスレッドが出力している途中なら書き終わるまで待ってからfalseを返すので，
+    /// falseが返ってきた時点で出力は済んでいる (すぐにプロセスを終えてよい)
+    pub fn emit_to<W: Write>(&self, out: &mut W) -> bool {
+        let mut emitted = self.emitted.lock().unwrap_or_else(|e| e.into_inner());
+        if *emitted {
+            return false;
+        }
+        let (_, output) = self.best();
+        writeln!(out, "{}", output).ok();
+        out.flush().ok();
+        *emitted = true;
+        true
+    }
+}
+
+/// solveをcatch_unwindで包んで動かし，返ってきた経路もnetに渡してから，netの最良の (得点, 出力) を返す
+/// solveがpanicしたら，それまでにnetに渡されていた最良のものを返す
+pub fn guard<F: FnOnce() -> (i32, Output)>(net: &SafetyNet, solve: F) -> (i32, Output) {
+    match catch_unwind(AssertUnwindSafe(solve)) {
+        Ok((_, output)) => {
+            net.offer(&output);
+        }
+        Err(_) => eprintln!("solver panicked; falling back to the best path found so far"),
+    }
+    net.best()
+}
+
+/// 見張りのスレッドを立てる
+/// get_time()がsoft_deadline(秒)を過ぎてもまだ出力していなければ，netの最良の経路を出力してプロセスを終了する
+pub fn spawn_watchdog(net: SafetyNet, soft_deadline: f64) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        // get_timeはlocalのとき実時間と進み方が違うので，少しずつ寝ながら見る
+        while get_time() < soft_deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        if net.emit() {
+            eprintln!("soft deadline {:.3}s passed; printed the best path found so far", soft_deadline);
+            std::process::exit(0);
+        }
+    })
+}
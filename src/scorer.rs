@@ -0,0 +1,35 @@
+use crate::*;
+
+/// 出力の得点を計算する
+/// TileStateを使わずに問題文の定義どおりに数えるので，探索側の実装を確かめるのに使う
+/// 盤面の外に出る・同じタイルを2回踏む・LRUD以外の文字があるときは何文字目かをErrで返す
+pub fn compute_score(input: &Input, output: &str) -> Result<i32, String> {
+    let M = input.tile_count();
+    let mut used = vec![false; M];
+    let (mut i, mut j) = input.s;
+    used[input.tiles[i][j]] = true;
+    let mut score = input.ps[i][j];
+    for (k, c) in output.trim_end().chars().enumerate() {
+        let (di, dj) = match c {
+            'L' => (0, -1),
+            'R' => (0, 1),
+            'U' => (-1, 0),
+            'D' => (1, 0),
+            _ => return Err(format!("{}: illegal character `{}`", k + 1, c)),
+        };
+        let ni = i as i64 + di;
+        let nj = j as i64 + dj;
+        if ni < 0 || nj < 0 || ni >= input.h as i64 || nj >= input.w as i64 {
+            return Err(format!("{}: out of the board ({}, {})", k + 1, ni, nj));
+        }
+        i = ni as usize;
+        j = nj as usize;
+        let tile = input.tiles[i][j];
+        if used[tile] {
+            return Err(format!("{}: tile {} is visited twice ({}, {})", k + 1, tile, i, j));
+        }
+        used[tile] = true;
+        score += input.ps[i][j];
+    }
+    Ok(score)
+}
@@ -0,0 +1,394 @@
+// [世界四連覇AIエンジニアがゼロから教えるゲーム木探索入門]
+// (https://qiita.com/thun-c/items/058743a25c37c87b8aa4)
+// を参考にしています。thunderさんに多大なる感謝を…
+// Copyright [2021] <Copyright Eita Aoki (Thunder) >
+//
+// src/bin/ainem.rs の1手ずつ決める探索を，SearchStateを実装した状態なら何にでも使えるようにしたもの
+// TileState以外のゲームでも SearchState を実装すれば同じ探索が使える
+
+use crate::*;
+use std::collections::{BinaryHeap, HashSet};
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// 探索で扱うゲームの状態
+/// ainem.rsで「[どのゲームでも実装する]」となっているメソッドをまとめたもの
+pub trait SearchState: Clone {
+    /// 問題の入力 (盤面など，探索中に変わらないもの)
+    type Input: ?Sized;
+    /// 現在の状況でプレイヤーが可能な行動を全て取得する
+    fn legal_actions(&self, input: &Self::Input) -> Actions;
+    /// 指定したactionでゲームを1ターン進める
+    fn advance(&mut self, input: &Self::Input, action: Action);
+    /// 探索用の盤面評価をする (大きいほど良い)
+    fn evaluate(&mut self) -> ScoreType;
+    /// ゲームの終了判定
+    fn is_done(&self) -> bool;
+    /// 探索木のルートノードで最初に選択した行動 (ルートならNone)
+    fn first_action(&self) -> Option<Action>;
+    fn set_first_action(&mut self, action: Action);
+}
+
+// ビームに入れる状態 (評価値の大きい順に取り出す)
+#[derive(Clone)]
+struct Node<S> {
+    score: ScoreType,
+    state: S,
+}
+
+impl<S> Node<S> {
+    fn new(mut state: S) -> Self
+    where
+        S: SearchState,
+    {
+        Self { score: state.evaluate(), state }
+    }
+}
+
+impl<S> Ord for Node<S> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score.cmp(&other.score)
+    }
+}
+impl<S> PartialEq for Node<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+impl<S> Eq for Node<S> {}
+impl<S> PartialOrd for Node<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// stateからactionで進めた子 (深さ0から進めるときはfirst_actionを覚えておく)
+fn child<S: SearchState>(input: &S::Input, state: &S, action: Action, is_root: bool) -> Node<S> {
+    let mut next_state = state.clone();
+    next_state.advance(input, action);
+    if is_root {
+        next_state.set_first_action(action);
+    }
+    Node::new(next_state)
+}
+
+/// ランダムに行動を決定する
+pub fn randomAction<S: SearchState>(rng: &mut Pcg64Mcg, input: &S::Input, state: &S) -> Option<Action> {
+    let legalActions = state.legal_actions(input);
+    if legalActions.is_empty() {
+        return None;
+    }
+    Some(legalActions[rng.gen_range(0, legalActions.len())])
+}
+
+/// 貪欲法で行動を決定する
+pub fn greedyAction<S: SearchState>(input: &S::Input, state: &S) -> Option<Action> {
+    let mut best_score: ScoreType = -INF;
+    let mut best_action = None;
+    for action in state.legal_actions(input) {
+        let now = child(input, state, action, false);
+        if now.score > best_score {
+            best_score = now.score;
+            best_action = Some(action);
+        }
+    }
+    best_action
+}
+
+/// ビーム幅と深さを指定してビームサーチで行動を決定する
+pub fn beamSearchAction<S: SearchState>(input: &S::Input, state: &S, beam_width: usize, beam_depth: usize) -> Option<Action> {
+    let mut now_beam = BinaryHeap::new();
+    let mut best = Node::new(state.clone());
+    now_beam.push(best.clone());
+    for t in 0..beam_depth {
+        let mut next_beam = BinaryHeap::new();
+        for _ in 0..beam_width {
+            let now = match now_beam.pop() {
+                Some(now) => now,
+                None => break,
+            };
+            for action in now.state.legal_actions(input) {
+                next_beam.push(child(input, &now.state, action, t == 0));
+            }
+        }
+
+        now_beam = next_beam;
+        // 注：ainem.rsでは次の状態がないとpeekでpanicする (盤面の端で行き詰まったとき)
+        match now_beam.peek() {
+            Some(node) => best = node.clone(),
+            None => break,
+        }
+        if best.state.is_done() {
+            break;
+        }
+    }
+    best.state.first_action()
+}
+
+/// ビーム幅と制限時間(s)を指定してビームサーチで行動を決定する
+pub fn beamSearchActionWithTimeThreshold<S: SearchState>(input: &S::Input, state: &S, beam_width: usize, time_threshold: f64) -> Option<Action> {
+    let start_time = get_time();
+    let mut now_beam = BinaryHeap::new();
+    let mut best = Node::new(state.clone());
+    now_beam.push(best.clone());
+
+    for t in 0.. {
+        let mut next_beam = BinaryHeap::new();
+        for _ in 0..beam_width {
+            if get_time() - start_time >= time_threshold {
+                return best.state.first_action();
+            }
+            let now = match now_beam.pop() {
+                Some(now) => now,
+                None => break,
+            };
+            for action in now.state.legal_actions(input) {
+                next_beam.push(child(input, &now.state, action, t == 0));
+            }
+        }
+
+        now_beam = next_beam;
+        match now_beam.peek() {
+            Some(node) => best = node.clone(),
+            None => break,
+        }
+        if best.state.is_done() {
+            break;
+        }
+    }
+    best.state.first_action()
+}
+
+// chokudaiサーチの1周分 (深さ0から順に，各深さの上位beam_width個を展開する)
+fn chokudaiSearchStep<S: SearchState>(input: &S::Input, beam: &mut [BinaryHeap<Node<S>>], beam_width: usize) {
+    // thunderさんのコードだとここで
+    // now_beam = &beam[t]
+    // next_beam = &beam[t+1]
+    // としていて、その方が見やすいのだが、Rustでは二重借用ができない
+    for t in 0..beam.len() - 1 {
+        for _ in 0..beam_width {
+            if let Some(now) = beam[t].pop() {
+                if now.state.is_done() {
+                    beam[t].push(now);
+                    break;
+                }
+                for action in now.state.legal_actions(input) {
+                    beam[t + 1].push(child(input, &now.state, action, t == 0));
+                }
+            }
+        }
+    }
+}
+
+// 一番深いところまで行けた状態のうち評価の高いものの最初の行動
+fn chokudaiSearchResult<S: SearchState>(beam: &[BinaryHeap<Node<S>>]) -> Option<Action> {
+    // 深さ0の状態はfirst_actionを持たないので見ない
+    for now_beam in beam[1..].iter().rev() {
+        if let Some(node) = now_beam.peek() {
+            return node.state.first_action();
+        }
+    }
+    None
+}
+
+/// ビーム1本あたりのビーム幅とビームの本数を指定してchokudaiサーチで行動を決定する
+pub fn chokudaiSearchAction<S: SearchState>(input: &S::Input, state: &S, beam_width: usize, beam_depth: usize, beam_number: usize) -> Option<Action> {
+    let mut beam = vec![BinaryHeap::new(); beam_depth + 1];
+    beam[0].push(Node::new(state.clone()));
+    for _ in 0..beam_number {
+        chokudaiSearchStep(input, &mut beam, beam_width);
+    }
+    chokudaiSearchResult(&beam)
+}
+
+/// ビーム1本あたりのビーム幅と制限時間(s)を指定してchokudaiサーチで行動を決定する
+pub fn chokudaiSearchActionWithTimeThreshold<S: SearchState>(input: &S::Input, state: &S, beam_width: usize, beam_depth: usize, time_threshold: f64) -> Option<Action> {
+    let start_time = get_time();
+    let mut beam = vec![BinaryHeap::new(); beam_depth + 1];
+    beam[0].push(Node::new(state.clone()));
+    loop {
+        chokudaiSearchStep(input, &mut beam, beam_width);
+        if get_time() - start_time >= time_threshold {
+            break;
+        }
+    }
+    chokudaiSearchResult(&beam)
+}
+
+/// stateから，行動を決める関数が行動を返さなくなるまで1手ずつ進める
+/// ainem.rsのmainのループ (制限時間はtime_limit秒，Noneなら行き詰まるまで)
+pub fn play<S: SearchState, F: FnMut(&S) -> Option<Action>>(input: &S::Input, mut state: S, time_limit: Option<f64>, mut choose_action: F) -> S {
+    let start_time = get_time();
+    while let Some(action) = choose_action(&state) {
+        if time_limit.is_some_and(|tl| get_time() - start_time >= tl) {
+            break;
+        }
+        state.advance(input, action);
+    }
+    state
+}
+
+// chokudaiSearchで深さごとに残しておく状態の数 (beam_widthの何倍か)
+const CHOKUDAI_KEEP_FACTOR: usize = 16;
+
+/// 盤面の同一判定に使うハッシュを返せる状態
+/// 同じハッシュの状態はchokudaiSearchの同じ深さで1つしか展開しない
+pub trait HashState {
+    fn hash(&self) -> u64;
+}
+
+/// 全体を一度だけchokudaiサーチして，一番よい経路の最後の状態を返す
+/// 深さごとに評価の高い順の状態を持ち，深さ0から順に各深さの上位beam_width個を展開するのを，
+/// beam_number周かtime_limit秒たつまで繰り返す (Noneの方は見ない．両方Noneなら展開しきるまで)
+/// 深さごとに展開した状態のハッシュの集合を持ち，同じ深さで同じハッシュの状態は評価の一番高いものだけ展開する
+/// 1手ずつ木を作り直すchokudaiSearchActionと違い，最後まで行けた経路をそのまま返すので焼きなましと比べられる
+pub fn chokudaiSearch<S: SearchState + HashState>(input: &S::Input, state: &S, beam_width: usize, beam_number: Option<usize>, time_limit: Option<f64>) -> S {
+    let start_time = get_time();
+    let root = Node::new(state.clone());
+    // (評価値, 深さ, 状態): 評価値が同じなら深い方を返す
+    let mut best = (root.score, 0, root.state.clone());
+    let mut beam: Vec<BinaryHeap<Node<S>>> = vec![BinaryHeap::new()];
+    let mut expanded: Vec<HashSet<u64>> = vec![HashSet::new()];
+    beam[0].push(root);
+    let is_time_over = || time_limit.is_some_and(|tl| get_time() - start_time >= tl);
+    let mut turn = 0;
+    'search: while beam_number.is_none_or(|n| turn < n) {
+        turn += 1;
+        let mut updated = false;
+        // 深さは展開するたびに伸びていく
+        let mut t = 0;
+        while t < beam.len() {
+            // 1周で経路の長さ分の深さを見るので，時間は深さごとに見る
+            if is_time_over() {
+                break 'search;
+            }
+            let mut cnt = 0;
+            while cnt < beam_width {
+                let now = match beam[t].pop() {
+                    Some(now) => now,
+                    None => break,
+                };
+                // 同じ深さで同じ盤面をすでに展開していたら飛ばす (先に出てきた方が評価が高い)
+                if !expanded[t].insert(now.state.hash()) {
+                    continue;
+                }
+                cnt += 1;
+                updated = true;
+                let actions = if now.state.is_done() { vec![] } else { now.state.legal_actions(input) };
+                // これ以上進めない状態は経路の候補
+                if actions.is_empty() {
+                    if (now.score, t) > (best.0, best.1) {
+                        best = (now.score, t, now.state);
+                    }
+                    continue;
+                }
+                if beam.len() == t + 1 {
+                    beam.push(BinaryHeap::new());
+                    expanded.push(HashSet::new());
+                }
+                for action in actions {
+                    beam[t + 1].push(child(input, &now.state, action, t == 0));
+                }
+                // 1周で各深さから取り出すのはbeam_width個なので，下位の状態はまず展開されない
+                // 全部持っているとすぐにメモリが足りなくなるので，溜まりすぎたら上位だけ残す
+                let keep = beam_width * CHOKUDAI_KEEP_FACTOR;
+                if beam[t + 1].len() > 2 * keep {
+                    let mut states = std::mem::take(&mut beam[t + 1]).into_sorted_vec();
+                    beam[t + 1] = states.split_off(states.len() - keep).into();
+                }
+            }
+            t += 1;
+        }
+        // 全部展開しきった
+        if !updated {
+            break;
+        }
+    }
+    // 打ち切ったときは，まだ展開していない状態の中からも探す
+    for (t, now_beam) in beam.iter().enumerate() {
+        if let Some(now) = now_beam.peek() {
+            if (now.score, t) > (best.0, best.1) {
+                best = (now.score, t, now.state.clone());
+            }
+        }
+    }
+    best.2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // 深さと行動の和だけを持つ状態．行動の順番が違っても和が同じなら同じ状態になる
+    // 入力には展開した(legal_actionsを呼んだ)状態の (深さ, ハッシュ) を記録する
+    #[derive(Clone)]
+    struct SumState {
+        depth: usize,
+        sum: usize,
+        // 深さ0で打てる手の数と，全体の深さ
+        root_actions: usize,
+        max_depth: usize,
+        first_action: Option<Action>,
+    }
+
+    impl SearchState for SumState {
+        type Input = RefCell<Vec<(usize, u64)>>;
+        fn legal_actions(&self, input: &Self::Input) -> Actions {
+            input.borrow_mut().push((self.depth, HashState::hash(self)));
+            if self.depth == 0 { (0..self.root_actions).collect() } else { vec![0, 1, 2] }
+        }
+        fn advance(&mut self, _input: &Self::Input, action: Action) {
+            self.depth += 1;
+            self.sum += action;
+        }
+        fn evaluate(&mut self) -> ScoreType {
+            self.sum as ScoreType
+        }
+        fn is_done(&self) -> bool {
+            self.depth == self.max_depth
+        }
+        fn first_action(&self) -> Option<Action> {
+            self.first_action
+        }
+        fn set_first_action(&mut self, action: Action) {
+            self.first_action = Some(action);
+        }
+    }
+
+    impl HashState for SumState {
+        fn hash(&self) -> u64 {
+            (self.depth * 1000 + self.sum) as u64
+        }
+    }
+
+    #[test]
+    fn chokudai_expands_each_state_once_per_depth() {
+        let log = RefCell::new(vec![]);
+        let state = SumState { depth: 0, sum: 0, root_actions: 3, max_depth: 5, first_action: None };
+        let best = chokudaiSearch(&log, &state, 100, None, None);
+        assert_eq!((best.depth, best.sum), (5, 10));
+        // 深さtで和は0~2tなので，区別できる状態は2t+1個 (経路は3^t通りある)
+        let log = log.into_inner();
+        for t in 0..5 {
+            let mut hashes: Vec<u64> = log.iter().filter(|&&(depth, _)| depth == t).map(|&(_, hash)| hash).collect();
+            let expanded = hashes.len();
+            hashes.sort();
+            hashes.dedup();
+            assert_eq!((expanded, hashes.len()), (2 * t + 1, 2 * t + 1), "depth {}", t);
+        }
+    }
+
+    #[test]
+    fn chokudai_keeps_only_the_top_states_per_depth() {
+        let log = RefCell::new(vec![]);
+        // beam_width 1で深さ1にkeepの2倍より多い子を作ると，上位keep個だけ残る
+        let keep = CHOKUDAI_KEEP_FACTOR;
+        let state = SumState { depth: 0, sum: 0, root_actions: 2 * keep + 8, max_depth: 2, first_action: None };
+        chokudaiSearch(&log, &state, 1, Some(1000), None);
+        let mut sums: Vec<usize> = log.into_inner().iter().filter(|&&(depth, _)| depth == 1).map(|&(_, hash)| hash as usize - 1000).collect();
+        sums.sort();
+        assert_eq!(sums, (keep + 8..2 * keep + 8).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::engine::DfsLimit;
+use crate::*;
+
+/// 区間の繋ぎ直しで見つかった経路
+/// score: p1から進んだ先(p2を含む)で得た得点
+/// actions: p1からp2までの行動列
+/// optimal: 時間切れにならず探索しきったか (trueなら証明付きで最善)
+#[derive(Clone, Debug)]
+pub struct SegmentPath {
+    pub score: i32,
+    pub actions: Actions,
+    pub optimal: bool,
+}
+
+// 分枝限定法の探索中に持ち回る情報をまとめたもの
+struct SegmentOptimizer<'a> {
+    input: &'a Input,
+    seen: &'a mut Vec<bool>,
+    // Noneなら終点は自由
+    goal: Option<(usize, usize)>,
+    start_time: f64,
+    limit: DfsLimit,
+    actions: Actions,
+    best: Option<(i32, Actions)>,
+    node_cnt: usize,
+    aborted: bool,
+    // 上界計算のBFS用の作業領域 (毎回確保しないようにstampで使い回す)
+    stamp: u32,
+    cell_stamp: Vec<Vec<u32>>,
+    tile_stamp: Vec<u32>,
+    tile_best: Vec<i32>,
+    queue: Vec<(usize, usize)>,
+}
+
+impl<'a> SegmentOptimizer<'a> {
+    /// (x, y)からgoalまでの残りで得られる得点の上界を返す
+    /// goalへたどり着けないならNone
+    /// (x, y)から踏んでいないタイルだけを通って到達できるマスを列挙し，タイルごとに最も高いマスの得点を足す
+    /// goalに着いたら終わりなので，goalから先へは広げない (goalがNoneなら常に到達できるとする)
+    fn upper_bound(&mut self, x: usize, y: usize) -> Option<i32> {
+        self.stamp += 1;
+        let stamp = self.stamp;
+        let input = self.input;
+        self.queue.clear();
+        self.queue.push((x, y));
+        self.cell_stamp[x][y] = stamp;
+        let mut head = 0;
+        let mut reach_goal = self.goal.is_none();
+        let mut bound = 0;
+        while head < self.queue.len() {
+            let (cx, cy) = self.queue[head];
+            head += 1;
+            if Some((cx, cy)) == self.goal {
+                reach_goal = true;
+                continue;
+            }
+            for &(dx, dy) in &DIJ {
+                let nx = cx.wrapping_add(dx);
+                let ny = cy.wrapping_add(dy);
+                if nx >= input.h || ny >= input.w || self.cell_stamp[nx][ny] == stamp {
+                    continue;
+                }
+                let tile = input.tiles[nx][ny];
+                if self.seen[tile] {
+                    continue;
+                }
+                self.cell_stamp[nx][ny] = stamp;
+                self.queue.push((nx, ny));
+                // 同じタイルは1マスしか踏めないので，タイル内で高い方だけを数える
+                let p = input.ps[nx][ny];
+                if self.tile_stamp[tile] != stamp {
+                    self.tile_stamp[tile] = stamp;
+                    self.tile_best[tile] = p;
+                    bound += p;
+                } else if self.tile_best[tile] < p {
+                    bound += p - self.tile_best[tile];
+                    self.tile_best[tile] = p;
+                }
+            }
+        }
+        if reach_goal {
+            Some(bound)
+        } else {
+            None
+        }
+    }
+
+    // 呼ばれる時点で(x, y)は踏んだ状態になっていて，scoreに加算済み
+    fn search(&mut self, x: usize, y: usize, score: i32) {
+        self.node_cnt += 1;
+        // ノード数の上限は毎回，get_timeは重いのでたまにだけ見る
+        if self.aborted
+            || self.limit.max_nodes.is_some_and(|max_nodes| self.node_cnt > max_nodes)
+            || ((self.node_cnt & 255) == 0 && self.limit.time_limit.is_some_and(|tl| get_time() - self.start_time > tl))
+        {
+            self.aborted = true;
+            return;
+        }
+        // goalに着いたらそこで終わり (goalがなければどこで終わってもよい)
+        if self.goal.is_none_or(|goal| (x, y) == goal) && self.best.as_ref().is_none_or(|b| b.0 < score) {
+            self.best = Some((score, self.actions.clone()));
+        }
+        if Some((x, y)) == self.goal {
+            return;
+        }
+        // 子ごとに「そこへ進んだときの得点 + 残りの上界」を求め，大きい順に見る (最良優先)
+        let input = self.input;
+        let mut children = vec![];
+        for (action, &(dx, dy)) in DIJ.iter().enumerate() {
+            let nx = x.wrapping_add(dx);
+            let ny = y.wrapping_add(dy);
+            if nx >= input.h || ny >= input.w || self.seen[input.tiles[nx][ny]] {
+                continue;
+            }
+            let next_score = score + input.ps[nx][ny];
+            self.seen[input.tiles[nx][ny]] = true;
+            let bound = if Some((nx, ny)) == self.goal {
+                Some(0)
+            } else {
+                self.upper_bound(nx, ny)
+            };
+            self.seen[input.tiles[nx][ny]] = false;
+            if let Some(bound) = bound {
+                children.push((next_score + bound, next_score, action, nx, ny));
+            }
+        }
+        children.sort_unstable_by(|a, b| b.cmp(a));
+        for (limit, next_score, action, nx, ny) in children {
+            // これまでの最善を超えられないなら枝刈り (上界の大きい順に見ているので残りも全部切れる)
+            if self.best.as_ref().is_some_and(|b| limit <= b.0) {
+                break;
+            }
+            self.seen[input.tiles[nx][ny]] = true;
+            self.actions.push(action);
+            self.search(nx, ny, next_score);
+            self.actions.pop();
+            self.seen[input.tiles[nx][ny]] = false;
+            if self.aborted {
+                return;
+            }
+        }
+    }
+}
+
+/// p1->p2を繋ぎ直す経路のうち，得点が最大のものを分枝限定法で探す
+/// seenはp1を踏んだ状態，p1より後ろ〜p2までの区間を踏んでいない状態にしておくこと
+/// (戻るときには呼ぶ前の状態に戻っている)
+/// 到達可能なマスの得点の和を上界として枝刈りするので，空いている領域が狭ければ最善が求まる
+/// limitで打ち切った場合はそれまでの最善をoptimal = falseで返す
+/// p2へ繋がる経路が見つからなければNone
+pub fn optimize_segment(input: &Input, seen: &mut Vec<bool>, p1: (usize, usize), p2: (usize, usize), limit: DfsLimit) -> Option<SegmentPath> {
+    optimize(input, seen, p1, Some(p2), limit)
+}
+
+/// p1から終点を決めずに進む経路のうち，得点が最大のものを分枝限定法で探す (optimize_segmentの終点が自由な版)
+/// p1から1歩も動かない経路(score = 0)も候補なので必ず経路を返す
+pub fn optimize_tail(input: &Input, seen: &mut Vec<bool>, p1: (usize, usize), limit: DfsLimit) -> SegmentPath {
+    optimize(input, seen, p1, None, limit).unwrap()
+}
+
+fn optimize(input: &Input, seen: &mut Vec<bool>, p1: (usize, usize), goal: Option<(usize, usize)>, limit: DfsLimit) -> Option<SegmentPath> {
+    let M = seen.len();
+    let mut optimizer = SegmentOptimizer {
+        input,
+        seen,
+        goal,
+        start_time: get_time(),
+        limit,
+        actions: vec![],
+        best: None,
+        node_cnt: 0,
+        aborted: false,
+        stamp: 0,
+        cell_stamp: vec![vec![0; input.w]; input.h],
+        tile_stamp: vec![0; M],
+        tile_best: vec![0; M],
+        queue: vec![],
+    };
+    optimizer.upper_bound(p1.0, p1.1)?;
+    optimizer.search(p1.0, p1.1, 0);
+    let optimal = !optimizer.aborted;
+    optimizer.best.map(|(score, actions)| SegmentPath { score, actions, optimal })
+}
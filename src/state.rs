@@ -0,0 +1,321 @@
+use crate::*;
+
+const VIEW_POINTS: bool = false; // デバッグの時得点を表示するかどうか
+
+#[derive(Clone)]
+/// 位置を表す構造体
+pub struct Position {
+    pub i_: usize,
+    pub j_: usize,
+}
+
+/// 経路を再生できなかった理由
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayErrorKind {
+    /// LRUD以外の文字
+    InvalidChar(char),
+    /// 盤面の外に出るか，踏んだタイルをもう一度踏む
+    IllegalMove(char),
+}
+
+/// 経路の再生エラー
+/// index: 何手目(0始まり)で失敗したか
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayError {
+    pub index: usize,
+    pub kind: ReplayErrorKind,
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            ReplayErrorKind::InvalidChar(c) => write!(f, "move {}: invalid character `{}`", self.index + 1, c),
+            ReplayErrorKind::IllegalMove(c) => write!(f, "move {}: `{}` leaves the board or revisits a tile", self.index + 1, c),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[derive(Clone)]
+/// END_TURN_: 探索を終了するターン<br>
+/// turn_: 現在のターン<br>
+/// seen_: タイルを踏んだかどうか<br>
+/// pos_: 現在位置<br>
+/// output_: 経路の出力<br>
+/// steps_: 移動経路の座標<br>
+/// game_score_: 得点(実際の得点)<br>
+/// evaluated_score_: 探索上で評価したスコア<br>
+/// first_action_: 探索木のルートノードで最初に選択した行動<br>
+pub struct TileState {
+    pub END_TURN_: usize,
+    pub turn_: usize,
+    pub seen_: Vec<bool>,
+    pub pos_: Position,
+    pub output_: Output,
+    pub steps_: Vec<(usize, usize)>,
+    pub game_score_: i32,
+    pub evaluated_score_: ScoreType,
+    pub first_action_: Action,
+}
+
+impl TileState {
+    pub fn new(input: &Input, end_turn: usize, pos: (usize, usize)) -> Self {
+        let M_ = input.tile_count();
+        let mut seen_ = vec![false; M_];
+        let pos_ = Position {
+            i_: pos.0,
+            j_: pos.1,
+        };
+        seen_[input.tiles[pos_.i_][pos_.j_]] = true;
+        let steps_ = vec![(pos_.i_, pos_.j_)];
+        let game_score_ = input.ps[pos_.i_][pos_.j_];
+        let evaluated_score_ = 0;
+
+        Self {
+            END_TURN_: end_turn,
+            turn_: 0,
+            seen_,
+            pos_,
+            steps_,
+            output_: String::new(),
+            game_score_,
+            evaluated_score_,
+            first_action_: !0,
+        }
+    }
+
+    /// [どのゲームでも実装する]: 探索用の盤面評価をする
+    /// 探索ではゲーム本来のスコアに別の評価値をプラスするといい探索ができるので、ここに工夫の余地がある。
+    pub fn evaluateScore(&mut self) {
+        self.evaluated_score_ = self.game_score_;
+    }
+
+    /// [どのゲームでも実装する]: ゲームの終了判定
+    pub fn isDone(&self) -> bool {
+        self.turn_ == self.END_TURN_
+    }
+
+    /// [どのゲームでも実装する]: 指定したactionでゲームを1ターン進める
+    pub fn advance(&mut self, input: &Input, action: Action) {
+        self.pos_.i_ = self.pos_.i_.wrapping_add(DIJ[action].0);
+        self.pos_.j_ = self.pos_.j_.wrapping_add(DIJ[action].1);
+        self.steps_.push((self.pos_.i_, self.pos_.j_));
+        self.game_score_ += input.ps[self.pos_.i_][self.pos_.j_];
+        self.seen_[input.tiles[self.pos_.i_][self.pos_.j_]] = true;
+        self.turn_ += 1;
+        self.output_.push(DIR[action]);
+    }
+
+    /// 経路をturn手目(steps_[turn])まで戻す
+    /// 戻した分だけ得点を引きseenを消すので，戻した手数に比例する時間で済む
+    pub fn retreatTo(&mut self, input: &Input, turn: usize) {
+        while self.turn_ > turn {
+            let (i, j) = self.steps_.pop().unwrap();
+            self.game_score_ -= input.ps[i][j];
+            self.seen_[input.tiles[i][j]] = false;
+            self.output_.pop();
+            self.turn_ -= 1;
+        }
+        let &(i, j) = self.steps_.last().unwrap();
+        self.pos_ = Position { i_: i, j_: j };
+    }
+
+    /// LRUDの文字列で表された経路をまとめて進める
+    /// 合法性は見ないので，検証したいときは呼び出し側でlegalActionsを確認すること
+    pub fn advanceByOutput(&mut self, input: &Input, output: &str) {
+        for c in output.chars() {
+            self.advance(input, char_to_action(c).unwrap());
+        }
+    }
+
+    /// LRUDの文字列で表された経路をsから1手ずつ合法か確かめながら進めた状態を作る
+    /// 以前の実行の出力を読み込んで初期解にするときなど，信用できない経路はこちらを使う
+    pub fn fromOutput(input: &Input, output: &str) -> Result<Self, ReplayError> {
+        let mut state = Self::new(input, !0, input.s);
+        for (index, c) in output.trim().chars().enumerate() {
+            let action = char_to_action(c).ok_or(ReplayError { index, kind: ReplayErrorKind::InvalidChar(c) })?;
+            if !state.legalActions(input).contains(&action) {
+                return Err(ReplayError { index, kind: ReplayErrorKind::IllegalMove(c) });
+            }
+            state.advance(input, action);
+        }
+        state.evaluateScore();
+        Ok(state)
+    }
+
+    /// [どのゲームでも実装する]: 現在の状況でプレイヤーが可能な行動を全て取得する
+    pub fn legalActions(&self, input: &Input) -> Actions {
+        let mut actions: Actions = vec![];
+        for action in 0..4 {
+            let ni = self.pos_.i_.wrapping_add(DIJ[action].0);
+            let nj = self.pos_.j_.wrapping_add(DIJ[action].1);
+            if ni < input.h && nj < input.w && !self.seen_[input.tiles[ni][nj]] {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+
+    /// [実装しなくてもよいが実装すると便利]: 現在のゲーム状況を標準エラー出力に出力する
+    pub fn toString(&self, input: &Input) {
+        let (H, W) = (input.h, input.w);
+        let mut path = vec![vec!["  "; W]; H];
+        let string: Vec<Vec<String>> = input
+            .ps
+            .iter()
+            .map(|pvec| pvec.iter().map(|p| format!("{:02}", p)).collect())
+            .collect();
+        if VIEW_POINTS {
+            for i in 0..H {
+                for j in 0..W {
+                    path[i][j] = string[i][j].as_str();
+                }
+            }
+        }
+        // 移動経路に罫線を引く
+        let (i, j) = input.s;
+        path[i][j] = "@@";
+        for i in 1..self.turn_ {
+            let (h, w) = self.steps_[i];
+            let mut dir = String::new();
+            dir.push(self.output_.chars().nth(i - 1).unwrap());
+            dir.push(self.output_.chars().nth(i).unwrap());
+            // 直前の移動方向 + 今回の移動方向によって引く罫線を決定
+            path[h][w] = match dir.as_str() {
+                "LL" => "━━",
+                "LU" => "┗━",
+                "LD" => "┏━",
+                "RR" => "━━",
+                "RU" => "┛ ",
+                "RD" => "┓ ",
+                "UL" => "┓ ",
+                "UR" => "┏━",
+                "UU" => "┃ ",
+                "DL" => "┛ ",
+                "DR" => "┗━",
+                "DD" => "┃ ",
+                _ => unreachable!(),
+            }
+        }
+        // 出力パート
+        let isConnectHorizontal =
+            |h: usize, w: usize| w + 1 < W && input.tiles[h][w] == input.tiles[h][w + 1];
+        let isConnectVertical =
+            |h: usize, w: usize| h + 1 < H && input.tiles[h][w] == input.tiles[h + 1][w];
+        for h in 0..H {
+            for w in 0..W {
+                if !isConnectVertical(h, w) {
+                    // 下のタイルとつながっていなかったら下線を引く
+                    eprint!("\x1b[4m");
+                }
+                if self.seen_[input.tiles[h][w]] {
+                    // 踏んだタイルなら色を塗る
+                    eprint!("\x1b[46m");
+                }
+                eprint!("{}", path[h][w]);
+                if isConnectHorizontal(h, w) {
+                    // 右のタイルと繋がっていたら文字修飾を引き継いで空白を出力
+                    eprint!(" ")
+                } else {
+                    // 右のタイルと繋がっていなかったら修飾をリセットして|を出力
+                    eprint!("\x1b[0m");
+                    eprint!("|");
+                }
+            }
+            eprintln!();
+        }
+        eprintln!("turn : {}", self.turn_);
+        eprintln!("score: {}", self.game_score_);
+    }
+}
+
+/// [どのゲームでも実装する] : 探索時のソート用に評価を比較する
+impl Ord for TileState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.evaluated_score_.cmp(&other.evaluated_score_)
+    }
+}
+impl PartialEq for TileState {
+    fn eq(&self, other: &Self) -> bool {
+        self.evaluated_score_ == other.evaluated_score_
+    }
+}
+impl Eq for TileState {} // ここは空でOK
+impl PartialOrd for TileState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl crate::search::SearchState for TileState {
+    type Input = Input;
+
+    fn legal_actions(&self, input: &Input) -> Actions {
+        self.legalActions(input)
+    }
+
+    fn advance(&mut self, input: &Input, action: Action) {
+        TileState::advance(self, input, action);
+    }
+
+    fn evaluate(&mut self) -> ScoreType {
+        self.evaluateScore();
+        self.evaluated_score_
+    }
+
+    fn is_done(&self) -> bool {
+        self.isDone()
+    }
+
+    fn first_action(&self) -> Option<Action> {
+        Some(self.first_action_).filter(|&action| action != !0)
+    }
+
+    fn set_first_action(&mut self, action: Action) {
+        self.first_action_ = action;
+    }
+}
+
+/// 現在位置と踏んだタイルの集合が同じなら，そこから先にできることは同じ
+impl crate::search::HashState for TileState {
+    fn hash(&self) -> u64 {
+        // rustc-hash(FxHash)と同じ混ぜ方 (seen_は64個ずつまとめて混ぜる)
+        let mut hash: u64 = 0;
+        let mut mix = |x: u64| {
+            hash = (hash.rotate_left(5) ^ x).wrapping_mul(0x517cc1b727220a95);
+        };
+        mix(self.pos_.i_ as u64);
+        mix(self.pos_.j_ as u64);
+        for chunk in self.seen_.chunks(64) {
+            mix(chunk.iter().enumerate().fold(0, |acc, (k, &b)| acc | (b as u64) << k));
+        }
+        hash
+    }
+}
+
+pub type State = TileState;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fromOutput_reports_the_failing_move() {
+        // 3x3 (左上の2マスだけが1枚のタイル)．sは左上
+        let input = Input::parse("0 0\n0 0 1\n2 3 4\n5 6 7\n1 1 2\n3 4 5\n6 7 8\n").unwrap();
+        let error = |output: &str| State::fromOutput(&input, output).err();
+        let at = |index, kind| Some(ReplayError { index, kind });
+        assert_eq!(error("DX"), at(1, ReplayErrorKind::InvalidChar('X')));
+        // 盤面の外に出る
+        assert_eq!(error("U"), at(0, ReplayErrorKind::IllegalMove('U')));
+        assert_eq!(error("DRRR"), at(3, ReplayErrorKind::IllegalMove('R')));
+        // 踏んだタイルをもう一度踏む (同じタイルの隣のマスも踏んだことになる)
+        assert_eq!(error("DU"), at(1, ReplayErrorKind::IllegalMove('U')));
+        assert_eq!(error("R"), at(0, ReplayErrorKind::IllegalMove('R')));
+        assert_eq!(error("DRU"), at(2, ReplayErrorKind::IllegalMove('U')));
+        // 前後の改行は無視する
+        let state = State::fromOutput(&input, "DRRU\n").unwrap();
+        assert_eq!((state.output_.as_str(), state.game_score_), ("DRRU", 1 + 3 + 4 + 5 + 2));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::*;
+
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+/// 経路のお尻を行ける方向がなくなるまで伸ばすときの手の選び方
+/// Random: 一様ランダム
+/// Greedy: 行き先の得点が高い手
+/// Warnsdorff: 行き先から先に進める方向が少ない手 (同じなら得点が高い手)．ナイトツアーのWarnsdorffの規則で，袋小路を後回しにしないので長く伸びやすい
+/// Lookahead(d): d手先まで全部見て，得点の和が最大になる手 (Lookahead(0)はGreedyと同じ．parseでは作らない)
+/// Random以外も同点の手はランダムに選ぶ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TailPolicy {
+    Random,
+    Greedy,
+    Warnsdorff,
+    Lookahead(usize),
+}
+
+impl TailPolicy {
+    /// "random", "greedy", "warnsdorff", "lookahead3" のような名前から作る
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(TailPolicy::Random),
+            "greedy" => Some(TailPolicy::Greedy),
+            "warnsdorff" => Some(TailPolicy::Warnsdorff),
+            _ => name.strip_prefix("lookahead").and_then(|d| d.parse().ok()).filter(|&d| d > 0).map(TailPolicy::Lookahead),
+        }
+    }
+}
+
+/// (i, j)から踏んでいないタイルへ進める方向の数 ((i, j)のタイルは踏んだことにする)
+/// Warnsdorffの規則で使う (dfs::DfsOrder::Warnsdorffからも使う)
+pub fn onward_moves(input: &Input, seen: &[bool], i: usize, j: usize) -> usize {
+    let tile = input.tiles[i][j];
+    DIJ.iter()
+        .filter(|&&(di, dj)| {
+            let ni = i.wrapping_add(di);
+            let nj = j.wrapping_add(dj);
+            ni < input.h && nj < input.w && input.tiles[ni][nj] != tile && !seen[input.tiles[ni][nj]]
+        })
+        .count()
+}
+
+// (i, j)からdepth手以内で得られる得点の最大 ((i, j)自身の得点は含まない)
+fn lookahead(input: &Input, seen: &mut Vec<bool>, i: usize, j: usize, depth: usize) -> i32 {
+    if depth == 0 {
+        return 0;
+    }
+    seen[input.tiles[i][j]] = true;
+    let mut best = 0;
+    for &(di, dj) in &DIJ {
+        let ni = i.wrapping_add(di);
+        let nj = j.wrapping_add(dj);
+        if ni < input.h && nj < input.w && !seen[input.tiles[ni][nj]] {
+            best = best.max(input.ps[ni][nj] + lookahead(input, seen, ni, nj, depth - 1));
+        }
+    }
+    seen[input.tiles[i][j]] = false;
+    best
+}
+
+/// policyに従ってstateの次の1手を選ぶ (行ける方向がなければNone)
+pub fn choose_tail_action(rng: &mut Pcg64Mcg, input: &Input, state: &State, policy: TailPolicy) -> Option<Action> {
+    let actions = state.legalActions(input);
+    if actions.is_empty() {
+        return None;
+    }
+    if policy == TailPolicy::Random {
+        return Some(actions[rng.gen_range(0, actions.len())]);
+    }
+    let mut seen = match policy {
+        TailPolicy::Lookahead(_) => state.seen_.clone(),
+        _ => vec![],
+    };
+    // 評価が最大の手を選ぶ (同点の手は等確率で選ぶ)
+    let mut best_action = actions[0];
+    let mut best_key = i32::MIN;
+    let mut ties = 0;
+    for &action in &actions {
+        let i = state.pos_.i_.wrapping_add(DIJ[action].0);
+        let j = state.pos_.j_.wrapping_add(DIJ[action].1);
+        let p = input.ps[i][j];
+        let key = match policy {
+            TailPolicy::Random => unreachable!(),
+            TailPolicy::Greedy => p,
+            // 得点は0~99なので，進める方向の数を優先して得点で同点を崩す
+            TailPolicy::Warnsdorff => -(onward_moves(input, &state.seen_, i, j) as i32) * 100 + p,
+            TailPolicy::Lookahead(depth) => p + lookahead(input, &mut seen, i, j, depth.saturating_sub(1)),
+        };
+        if key > best_key {
+            best_key = key;
+            best_action = action;
+            ties = 1;
+        } else if key == best_key {
+            ties += 1;
+            if rng.gen_range(0, ties) == 0 {
+                best_action = action;
+            }
+        }
+    }
+    Some(best_action)
+}
+
+/// policyに従って行ける方向がなくなるまでお尻を伸ばす
+/// rollouts > 1 なら，今の状態からrollouts回伸ばしてみて得点が一番高かったものを採用する
+pub fn extend_tail_with(rng: &mut Pcg64Mcg, input: &Input, state: &mut State, policy: TailPolicy, rollouts: usize) {
+    // 焼きなましでは伸ばせないことがほとんどなので，cloneする前に見ておく
+    if state.legalActions(input).is_empty() {
+        return;
+    }
+    if rollouts <= 1 {
+        while let Some(action) = choose_tail_action(rng, input, state, policy) {
+            state.advance(input, action);
+        }
+        return;
+    }
+    let mut best: Option<State> = None;
+    for _ in 0..rollouts {
+        let mut next_state = state.clone();
+        while let Some(action) = choose_tail_action(rng, input, &next_state, policy) {
+            next_state.advance(input, action);
+        }
+        if best.as_ref().is_none_or(|best| next_state.game_score_ > best.game_score_) {
+            best = Some(next_state);
+        }
+    }
+    *state = best.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate;
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(TailPolicy::parse("warnsdorff"), Some(TailPolicy::Warnsdorff));
+        assert_eq!(TailPolicy::parse("lookahead3"), Some(TailPolicy::Lookahead(3)));
+        for name in ["lookahead0", "lookahead", "lookahead-1", "best"] {
+            assert_eq!(TailPolicy::parse(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn lookahead_zero_acts_like_greedy() {
+        let input = generate(0, 8, 8);
+        let state = State::new(&input, !0, input.s);
+        let mut rng = Pcg64Mcg::new(0);
+        let action = choose_tail_action(&mut rng, &input, &state, TailPolicy::Lookahead(0)).unwrap();
+        let best = state.legalActions(&input).into_iter().map(|a| input.ps[input.s.0.wrapping_add(DIJ[a].0)][input.s.1.wrapping_add(DIJ[a].1)]).max().unwrap();
+        assert_eq!(input.ps[input.s.0.wrapping_add(DIJ[action].0)][input.s.1.wrapping_add(DIJ[action].1)], best);
+    }
+}
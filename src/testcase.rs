@@ -0,0 +1,302 @@
+use crate::input::ParseError;
+use crate::*;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// テストケース1つ分
+/// name: どのファイルの何番目か (ベンチの表示やテストの失敗メッセージ用)
+/// expected_output: ファイルに書いてある出力例 (この問題では正解は1つではないので参考程度)
+#[derive(Clone, Debug)]
+pub struct TestCase {
+    pub name: String,
+    pub input: Input,
+    pub expected_output: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// ファイルが読めない
+    Io(PathBuf, std::io::Error),
+    /// cargo-competeのyamlとして読めない (行番号は1始まり)
+    Yaml(PathBuf, usize, String),
+    /// 問題の入力として正しくない
+    Input(String, ParseError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            LoadError::Yaml(path, line, msg) => write!(f, "{}:{}: {}", path.display(), line, msg),
+            LoadError::Input(name, e) => write!(f, "{}: {}", name, e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+fn read_to_string(path: &Path) -> Result<String, LoadError> {
+    std::fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))
+}
+
+fn parse_input(name: &str, src: &str) -> Result<Input, LoadError> {
+    Input::parse(src).map_err(|e| LoadError::Input(name.to_string(), e))
+}
+
+/// パスの種類を見て読み分ける
+/// ディレクトリなら中の *.yml と *.txt を全部，.ymlならcargo-competeのテストケース，それ以外は入力そのものとして読む
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<TestCase>, LoadError> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        load_dir(path)
+    } else if path.extension().is_some_and(|ext| ext == "yml" || ext == "yaml") {
+        load_yaml(path)
+    } else {
+        Ok(vec![load_txt(path)?])
+    }
+}
+
+/// ディレクトリ直下の *.yml と *.txt を名前順に全部読む
+pub fn load_dir(dir: &Path) -> Result<Vec<TestCase>, LoadError> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir).map_err(|e| LoadError::Io(dir.to_path_buf(), e))? {
+        let path = entry.map_err(|e| LoadError::Io(dir.to_path_buf(), e))?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "yml" || ext == "yaml" || ext == "txt") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let mut cases = vec![];
+    for path in paths {
+        cases.extend(load(&path)?);
+    }
+    Ok(cases)
+}
+
+/// 入力だけが書かれたテキストファイルを読む (名前はファイル名)
+pub fn load_txt(path: &Path) -> Result<TestCase, LoadError> {
+    let name = path.display().to_string();
+    let input = parse_input(&name, &read_to_string(path)?)?;
+    Ok(TestCase { name, input, expected_output: None })
+}
+
+/// cargo-competeのテストケースのyamlを読む
+/// cases: の in / out と，extend: の type: Text (pathのディレクトリ以下の in/*.txt, out/*.txt) に対応している
+/// 名前は cases が "ファイル名#番号(1始まり)"，extend がテキストファイルのパス
+pub fn load_yaml(path: &Path) -> Result<Vec<TestCase>, LoadError> {
+    let src = read_to_string(path)?;
+    let yaml_error = |line: usize, msg: &str| LoadError::Yaml(path.to_path_buf(), line, msg.to_string());
+    let sections = parse_yaml_lists(&src).map_err(|(line, msg)| yaml_error(line, &msg))?;
+    let mut cases = vec![];
+    for (section, items) in sections {
+        for item in items {
+            let get = |key: &str| item.values.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+            if section == "cases" {
+                let name = format!("{}#{}", path.display(), cases.len() + 1);
+                let src = get("in").ok_or_else(|| yaml_error(item.line, "case without `in`"))?;
+                cases.push(TestCase { input: parse_input(&name, &src)?, name, expected_output: get("out") });
+            } else if section == "extend" && get("type").as_deref() == Some("Text") {
+                let base = path.parent().unwrap_or(Path::new(".")).join(get("path").unwrap_or_default().trim_start_matches("./"));
+                let in_pattern = get("in").ok_or_else(|| yaml_error(item.line, "extend without `in`"))?;
+                let out_pattern = get("out");
+                for in_path in glob(&base, &in_pattern).map_err(|msg| yaml_error(item.line, &msg))? {
+                    let mut case = load_txt(&in_path)?;
+                    // 出力も同じファイル名で置いてあれば読む
+                    if let Some((out_dir, _)) = out_pattern.as_deref().and_then(split_pattern) {
+                        let out_path = base.join(out_dir).join(in_path.file_name().unwrap());
+                        case.expected_output = std::fs::read_to_string(out_path).ok();
+                    }
+                    cases.push(case);
+                }
+            }
+        }
+    }
+    Ok(cases)
+}
+
+// "/in/*.txt" を ("in", ".txt") に分ける
+fn split_pattern(pattern: &str) -> Option<(&str, &str)> {
+    let pattern = pattern.trim_start_matches('/');
+    let (dir, file) = pattern.rsplit_once('/').unwrap_or(("", pattern));
+    file.strip_prefix('*').map(|suffix| (dir, suffix))
+}
+
+// base/dir/*suffix に当てはまるファイルを名前順に返す (ディレクトリがなければ空)
+fn glob(base: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let (dir, suffix) = split_pattern(pattern).ok_or_else(|| format!("unsupported pattern `{}`", pattern))?;
+    let dir = base.join(dir);
+    let mut paths = vec![];
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with(suffix)) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+// トップレベルのキーとその下のリスト
+type YamlSection = (String, Vec<YamlItem>);
+
+// yamlのリストの要素1つ (キーと値の組と，要素が始まった行)
+struct YamlItem {
+    line: usize,
+    values: Vec<(String, String)>,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// cargo-competeのyamlで使われる範囲だけを読む簡易パーサ
+/// トップレベルの「key:」の下にある「- key: value」のリストを (key, 要素たち) にして返す
+/// 値は文字列そのもの，クォートされた文字列，ブロック(| / |- / |+)に対応する
+fn parse_yaml_lists(src: &str) -> Result<Vec<YamlSection>, (usize, String)> {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut sections: Vec<YamlSection> = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            i += 1;
+            continue;
+        }
+        let indent = indent_of(line);
+        if indent == 0 {
+            // トップレベルのキー (リストが続くものだけ覚えておく)
+            let key = trimmed.split(':').next().unwrap().trim().to_string();
+            sections.push((key, vec![]));
+            i += 1;
+            continue;
+        }
+        let items = match sections.last_mut() {
+            Some((_, items)) => items,
+            None => return Err((i + 1, "unexpected indentation".to_string())),
+        };
+        // 「- 」で新しい要素が始まる．中身のキーのインデントは「- 」の分だけ深い
+        let (key_indent, body) = if let Some(rest) = trimmed.strip_prefix("- ") {
+            items.push(YamlItem { line: i + 1, values: vec![] });
+            (indent + 2 + indent_of(rest), rest.trim_start())
+        } else {
+            (indent, trimmed)
+        };
+        let item = match items.last_mut() {
+            Some(item) => item,
+            None => return Err((i + 1, "expected a list item (`- `)".to_string())),
+        };
+        let (key, value) = match body.split_once(':') {
+            Some((key, value)) => (key.trim().to_string(), value.trim()),
+            None => return Err((i + 1, format!("expected `key: value`, found `{}`", body))),
+        };
+        i += 1;
+        if let Some(chomp) = value.strip_prefix('|') {
+            // ブロック: キーより深くインデントされた行が続く間が中身
+            let mut block: Vec<&str> = vec![];
+            let mut block_indent = None;
+            while i < lines.len() {
+                let l = lines[i];
+                if l.trim().is_empty() {
+                    block.push("");
+                    i += 1;
+                    continue;
+                }
+                let ind = indent_of(l);
+                if ind <= key_indent {
+                    break;
+                }
+                let ind = *block_indent.get_or_insert(ind);
+                if indent_of(l) < ind {
+                    return Err((i + 1, "inconsistent indentation in block".to_string()));
+                }
+                block.push(&l[ind..]);
+                i += 1;
+            }
+            let mut text = block.join("\n");
+            match chomp {
+                // 末尾の改行を全部落とす
+                "-" => text = text.trim_end_matches('\n').to_string(),
+                // 末尾の改行をそのまま残す
+                "+" => text.push('\n'),
+                // 末尾の改行を1つにする
+                _ => {
+                    text = text.trim_end_matches('\n').to_string();
+                    text.push('\n');
+                }
+            }
+            item.values.push((key, text));
+        } else {
+            let value = value.trim_matches(|c| c == '"' || c == '\'').to_string();
+            item.values.push((key, value));
+        }
+    }
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // parse_yaml_listsの結果の最初のセクションの最初の要素のkeyの値
+    fn first_value(src: &str, key: &str) -> String {
+        let sections = parse_yaml_lists(src).unwrap();
+        let item = &sections[0].1[0];
+        item.values.iter().find(|(k, _)| k == key).unwrap().1.clone()
+    }
+
+    #[test]
+    fn block_scalars_follow_chomping() {
+        // 中身の後ろに空行が2つある
+        let block = |indicator: &str| format!("cases:\n  - in: {}\n      1 2\n        3\n\n\n    out: x\n", indicator);
+        // |: 末尾の改行は1つ (ブロックの中の深いインデントはそのまま残る)
+        assert_eq!(first_value(&block("|"), "in"), "1 2\n  3\n");
+        // |-: 末尾の改行を落とす
+        assert_eq!(first_value(&block("|-"), "in"), "1 2\n  3");
+        // |+: 末尾の改行を全部残す
+        assert_eq!(first_value(&block("|+"), "in"), "1 2\n  3\n\n\n");
+        // ブロックの後のキーも読める
+        assert_eq!(first_value(&block("|"), "out"), "x");
+    }
+
+    #[test]
+    fn plain_and_quoted_values() {
+        let src = "---\n# comment\ntype: Batch\ncases:\n  - in: \"0 0\"\n    out: 'LR'\n  -   in: 1 1\n";
+        let sections = parse_yaml_lists(src).unwrap();
+        let keys: Vec<&str> = sections.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["type", "cases"]);
+        let items = &sections[1].1;
+        assert_eq!((items[0].line, items[1].line), (5, 7));
+        assert_eq!(items[0].values, vec![("in".to_string(), "0 0".to_string()), ("out".to_string(), "LR".to_string())]);
+        assert_eq!(items[1].values, vec![("in".to_string(), "1 1".to_string())]);
+    }
+
+    #[test]
+    fn yaml_errors_report_the_line() {
+        let cases = [
+            ("  in: 1\n", 1, "unexpected indentation"),
+            ("cases:\n  in: 1\n", 2, "expected a list item (`- `)"),
+            ("cases:\n  - in: 1\n  - foo\n", 3, "expected `key: value`, found `foo`"),
+            ("cases:\n  - in: |\n      1\n     2\n", 4, "inconsistent indentation in block"),
+        ];
+        for (src, line, msg) in cases {
+            assert_eq!(parse_yaml_lists(src).err(), Some((line, msg.to_string())), "{:?}", src);
+        }
+    }
+
+    #[test]
+    fn loads_bundled_testcase() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testcases").join("0.yml");
+        let cases = load(&path).unwrap();
+        // testcases/0/ はないのでextendからは何も増えない
+        assert_eq!(cases.len(), 1);
+        let case = &cases[0];
+        assert_eq!(case.name, format!("{}#1", path.display()));
+        assert_eq!((case.input.h, case.input.w), (50, 50));
+        let output = case.expected_output.as_deref().unwrap().trim();
+        assert!(output.starts_with("LDDDDDLL"));
+        assert!(State::fromOutput(&case.input, output).is_ok());
+    }
+}
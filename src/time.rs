@@ -0,0 +1,19 @@
+use std::sync::OnceLock;
+
+/// 最初に呼ばれた時点からの経過時間(秒)を返す
+/// 提出用ファイルでは static mut で持っていたが，テストを並列に走らせるのでOnceLockにしている
+pub fn get_time() -> f64 {
+    static STIME: OnceLock<f64> = OnceLock::new();
+    let t = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap();
+    let ms = t.as_secs() as f64 + t.subsec_nanos() as f64 * 1e-9;
+    let stime = *STIME.get_or_init(|| ms);
+    // ローカル環境とジャッジ環境の実行速度差はget_timeで吸収しておくと便利
+    #[cfg(feature="local")]
+    {
+        (ms - stime) * 10.0
+    }
+    #[cfg(not(feature="local"))]
+    {
+        ms - stime
+    }
+}
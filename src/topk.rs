@@ -0,0 +1,262 @@
+use crate::*;
+use std::cmp::Reverse;
+
+/// 候補同士の距離を測る関数
+pub type DistanceFn<T> = fn(&T, &T) -> usize;
+
+/// スコアの高い候補をk個まで持っておく入れ物
+/// DFSの途中で見つかった経路を全部heapに積むとすぐにMLEになるので，上位k個に入るときだけcloneして持つ
+/// diversityを設定すると，距離がmin_dist未満の候補同士はスコアの高い方しか残さない (似た経路ばかりになるのを防ぐ)
+#[derive(Clone)]
+pub struct TopK<T> {
+    k: usize,
+    items: Vec<(ScoreType, T)>,
+    diversity: Option<(usize, DistanceFn<T>)>,
+}
+
+impl<T: Clone> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            items: Vec::with_capacity(k + 1),
+            diversity: None,
+        }
+    }
+
+    /// 距離関数distで測ってmin_dist未満しか離れていない候補は同時に持たないようにする
+    pub fn with_diversity(k: usize, min_dist: usize, dist: DistanceFn<T>) -> Self {
+        Self {
+            diversity: Some((min_dist, dist)),
+            ..Self::new(k)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// k個埋まっているときの最低スコア (これ以下のスコアは入らない)
+    pub fn threshold(&self) -> Option<ScoreType> {
+        if self.items.len() < self.k {
+            None
+        } else {
+            self.items.iter().map(|item| item.0).min()
+        }
+    }
+
+    /// scoreの候補が入る見込みがあるか (cloneする前に見る用)
+    /// diversityがあるときは，trueでも似た候補に負けて入らないことがある
+    pub fn accepts(&self, score: ScoreType) -> bool {
+        self.k > 0 && self.threshold().is_none_or(|min_score| score > min_score)
+    }
+
+    /// 候補を参照で渡し，上位k個に入るときだけcloneして持つ
+    /// 入ったらtrueを返す
+    pub fn offer(&mut self, score: ScoreType, item: &T) -> bool {
+        if !self.make_room(score, item) {
+            return false;
+        }
+        self.insert(score, item.clone());
+        true
+    }
+
+    /// 値で渡す版
+    pub fn push(&mut self, score: ScoreType, item: T) -> bool {
+        if !self.make_room(score, &item) {
+            return false;
+        }
+        self.insert(score, item);
+        true
+    }
+
+    // 候補が入れるか判定し，入れるなら近すぎる候補を追い出しておく
+    fn make_room(&mut self, score: ScoreType, item: &T) -> bool {
+        if !self.accepts(score) {
+            return false;
+        }
+        if let Some((min_dist, dist)) = self.diversity {
+            // 近すぎる候補のうち1つでも自分以上のスコアなら入らない
+            // そうでなければ近すぎる候補は全部自分より悪いので追い出す
+            if self.items.iter().any(|(s, other)| *s >= score && dist(other, item) < min_dist) {
+                return false;
+            }
+            self.items.retain(|(_, other)| dist(other, item) >= min_dist);
+        }
+        true
+    }
+
+    fn insert(&mut self, score: ScoreType, item: T) {
+        self.items.push((score, item));
+        if self.items.len() > self.k {
+            // 最低スコアのものを落とす (同点なら古いものを残す)
+            let worst = (0..self.items.len()).rev().min_by_key(|&i| self.items[i].0).unwrap();
+            self.items.remove(worst);
+        }
+    }
+
+    /// 最高スコアの候補
+    pub fn best(&self) -> Option<&(ScoreType, T)> {
+        self.items.iter().max_by_key(|item| item.0)
+    }
+
+    /// スコアの高い順に並べて取り出す
+    pub fn into_sorted_vec(mut self) -> Vec<(ScoreType, T)> {
+        self.items.sort_by_key(|item| Reverse(item.0));
+        self.items
+    }
+}
+
+/// action列同士のハミング距離 (長さが違う分も違うものとして数える)
+pub fn hamming_distance(a: &Actions, b: &Actions) -> usize {
+    let diff = a.iter().zip(b.iter()).filter(|(x, y)| x != y).count();
+    diff + a.len().max(b.len()) - a.len().min(b.len())
+}
+
+/// 同じマスから出発したaction列同士で，片方の経路にしかない辺の数
+/// ハミング距離だと途中で1手ずれただけで後ろが全部違うことになるので，経路の形の違いを見たいときはこちらを使う
+pub fn edge_distance(a: &Actions, b: &Actions) -> usize {
+    let ea = path_edges(a);
+    let eb = path_edges(b);
+    // 両方ソート済みなのでマージしながら共通の辺を数える
+    let (mut x, mut y, mut common) = (0, 0, 0);
+    while x < ea.len() && y < eb.len() {
+        match ea[x].cmp(&eb[y]) {
+            std::cmp::Ordering::Less => x += 1,
+            std::cmp::Ordering::Greater => y += 1,
+            std::cmp::Ordering::Equal => {
+                common += 1;
+                x += 1;
+                y += 1;
+            }
+        }
+    }
+    ea.len() + eb.len() - 2 * common
+}
+
+// 出発点を(0, 0)とした経路の辺 (向きは区別しない) をソートして返す
+fn path_edges(actions: &Actions) -> Vec<((i32, i32), (i32, i32))> {
+    let mut pos = (0, 0);
+    let mut edges: Vec<_> = actions
+        .iter()
+        .map(|&action| {
+            let next = (pos.0 + DIJ[action].0 as i32, pos.1 + DIJ[action].1 as i32);
+            let edge = (pos.min(next), pos.max(next));
+            pos = next;
+            edge
+        })
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // cloneされた回数を数える候補
+    struct Counted;
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|c| c.set(c.get() + 1));
+            Counted
+        }
+    }
+
+    fn scores<T: Clone>(top: TopK<T>) -> Vec<ScoreType> {
+        top.into_sorted_vec().into_iter().map(|item| item.0).collect()
+    }
+
+    #[test]
+    fn keeps_at_most_k_best() {
+        let mut top = TopK::new(3);
+        for score in [5, 1, 9, 3, 7, 2, 8] {
+            top.push(score, score);
+        }
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.threshold(), Some(7));
+        assert_eq!(top.best(), Some(&(9, 9)));
+        assert_eq!(scores(top), vec![9, 8, 7]);
+        // k=0なら何も入らない
+        let mut empty = TopK::new(0);
+        assert!(!empty.accepts(100));
+        assert!(!empty.push(100, ()));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn ties_keep_the_older_item() {
+        let mut top = TopK::new(2);
+        assert!(top.push(3, "a"));
+        assert!(top.push(3, "b"));
+        // 埋まっていれば最低スコアと同点では入らない
+        assert!(!top.push(3, "c"));
+        // 押し出すときは同点の最低スコアのうち新しい方を落とす
+        assert!(top.push(4, "d"));
+        assert_eq!(top.into_sorted_vec(), vec![(4, "d"), (3, "a")]);
+        // 並べるときも同点は入れた順
+        let mut top = TopK::new(3);
+        for name in ["x", "y", "z"] {
+            top.push(1, name);
+        }
+        assert_eq!(top.into_sorted_vec(), vec![(1, "x"), (1, "y"), (1, "z")]);
+    }
+
+    #[test]
+    fn offer_clones_only_accepted_items() {
+        CLONES.with(|c| c.set(0));
+        let mut top = TopK::new(1);
+        assert!(top.offer(10, &Counted));
+        assert!(!top.accepts(5));
+        assert!(!top.offer(5, &Counted));
+        assert!(!top.offer(10, &Counted));
+        assert!(top.accepts(20));
+        assert!(top.offer(20, &Counted));
+        assert_eq!(CLONES.with(|c| c.get()), 2);
+        // pushは値で受け取るのでcloneしない
+        assert!(top.push(30, Counted));
+        assert_eq!(CLONES.with(|c| c.get()), 2);
+    }
+
+    #[test]
+    fn hamming_diversity_evicts_close_worse_items() {
+        let mut top = TopK::with_diversity(3, 2, hamming_distance);
+        assert!(top.push(10, vec![0, 0, 0]));
+        // 近くにもっと良いものがあれば入らない
+        assert!(!top.push(9, vec![0, 0, 1]));
+        assert!(top.push(5, vec![1, 1, 0]));
+        // 近くの悪いものは追い出して入る
+        assert!(top.push(12, vec![0, 0, 1]));
+        assert_eq!(top.into_sorted_vec(), vec![(12, vec![0, 0, 1]), (5, vec![1, 1, 0])]);
+        // 長さの差も違いとして数える
+        assert_eq!(hamming_distance(&vec![0, 1], &vec![0, 1, 2, 3]), 2);
+    }
+
+    #[test]
+    fn edge_diversity_compares_path_shapes() {
+        // 右下左 と 下右上 は2辺を共有する (ハミング距離だと全部違う)
+        let rdl = vec![1, 3, 0];
+        let dru = vec![3, 1, 2];
+        assert_eq!(edge_distance(&rdl, &dru), 2);
+        assert_eq!(hamming_distance(&rdl, &dru), 3);
+        assert_eq!(edge_distance(&rdl, &rdl), 0);
+        // 延ばした分だけ離れる
+        assert_eq!(edge_distance(&vec![1, 1], &vec![1, 1, 3, 3]), 2);
+
+        let mut top = TopK::with_diversity(3, 3, edge_distance);
+        assert!(top.push(10, rdl.clone()));
+        assert!(!top.push(9, dru.clone()));
+        assert!(top.push(11, dru.clone()));
+        assert!(top.push(5, vec![0, 0, 0]));
+        assert_eq!(top.into_sorted_vec(), vec![(11, dru), (5, vec![0, 0, 0])]);
+    }
+}
@@ -0,0 +1,94 @@
+#![allow(non_snake_case)]
+
+// 盤面ごとの最良解(best::BestKnown)の書き込み・読み込みを確かめる
+// 最良解はテストごとに一時ディレクトリの下に書く
+
+use ahc002::best::{instance_hash, BestEntry, BestError, BestKnown};
+use ahc002::generator::generate;
+use ahc002::scorer::compute_score;
+use ahc002::*;
+
+mod common;
+use common::{random_path, temp_dir};
+
+// 盤面と，sからランダムに行き詰まるまで歩いた経路
+fn random_output(seed: u64) -> (Input, Output) {
+    let (input, state) = random_path(seed, 10, 10);
+    (input, state.output_)
+}
+
+#[test]
+fn update_then_get() {
+    let dir = temp_dir("best-round-trip");
+    let best = BestKnown::open(dir.join("best"));
+    let (input, output) = random_output(1);
+    let score = compute_score(&input, &output).unwrap();
+    // ディレクトリがなくてもまだ最良解がないだけ
+    assert!(best.get(&input).unwrap().is_none());
+    assert!(best.update(&input, "annealing+bb", &output).unwrap());
+    let entry = best.get(&input).unwrap();
+    assert_eq!(entry, Some(BestEntry { score, strategy: "annealing+bb".to_string(), output }));
+    // 別の盤面の最良解にはならない
+    assert!(best.get(&generate(2, 10, 10)).unwrap().is_none());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn update_keeps_a_better_or_equal_entry() {
+    let dir = temp_dir("best-keep");
+    let best = BestKnown::open(&dir);
+    let (input, output) = random_output(3);
+    let score = compute_score(&input, &output).unwrap();
+    // 途中までの経路はもとの経路より得点が低い
+    let shorter = &output[..output.len() / 2];
+    assert!(compute_score(&input, shorter).unwrap() < score);
+
+    assert!(best.update(&input, "first", &output).unwrap());
+    assert!(!best.update(&input, "worse", shorter).unwrap());
+    assert!(!best.update(&input, "equal", &output).unwrap());
+    assert_eq!(best.get(&input).unwrap().map(|entry| (entry.score, entry.strategy)), Some((score, "first".to_string())));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn update_rejects_invalid_outputs() {
+    let dir = temp_dir("best-invalid");
+    let best = BestKnown::open(&dir);
+    let (input, output) = random_output(4);
+    // 知らない文字・盤面の外に出る・同じタイルを2回踏む
+    let back = match output.chars().next().unwrap() {
+        'L' => 'R',
+        'R' => 'L',
+        'U' => 'D',
+        _ => 'U',
+    };
+    let invalid = ["X".to_string(), "L".repeat(input.w), format!("{}{}", &output[..1], back)];
+    for output in &invalid {
+        match best.update(&input, "invalid", output) {
+            Err(BestError::Format(..)) => {}
+            other => panic!("{:?}: {:?}", output, other),
+        }
+    }
+    assert!(best.get(&input).unwrap().is_none());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn get_rejects_a_tampered_score() {
+    let dir = temp_dir("best-tampered");
+    let best = BestKnown::open(&dir);
+    let (input, output) = random_output(5);
+    let score = compute_score(&input, &output).unwrap();
+    assert!(best.update(&input, "annealing", &output).unwrap());
+    // 記録された得点だけを書き換える
+    let path = dir.join(format!("{:016x}.txt", instance_hash(&input)));
+    let src = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, src.replace(&format!("score {}", score), &format!("score {}", score + 1))).unwrap();
+    match best.get(&input) {
+        Err(BestError::Format(p, msg)) => assert_eq!((p, msg), (path.clone(), format!("recorded score {} but the output scores {}", score + 1, score))),
+        other => panic!("{:?}", other),
+    }
+    // 壊れた最良解は黙って上書きしない
+    assert!(best.update(&input, "annealing", &output).is_err());
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,49 @@
+// 複数のテストで使う設定と道具
+// テストのファイルごとに使うものが違うので，使われないものがあっても警告しない
+#![allow(dead_code)]
+
+use ahc002::annealing::{extend_tail, AnnealingConfig};
+use ahc002::engine::DfsLimit;
+use ahc002::generator::generate;
+use ahc002::*;
+use rand_pcg::Pcg64Mcg;
+use std::path::PathBuf;
+
+/// 時間に依存しない焼きなましの設定 (ノード数と反復回数で打ち切るので結果が再現する)
+pub fn annealing_config() -> AnnealingConfig {
+    AnnealingConfig {
+        time_limit: f64::INFINITY,
+        max_iterations: Some(2000),
+        first_dfs_limit: DfsLimit::nodes(20000),
+        reroute_limit: DfsLimit::nodes(2000),
+        ..Default::default()
+    }
+}
+
+/// テストごとの一時ディレクトリ (前回の実行の残りは消しておく)
+/// nameはテストの間で重ならないように付ける
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ahc002-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 盤面をseedから作り，sからランダムに行き詰まるまで歩いた経路を返す
+pub fn random_path(seed: u64, h: usize, w: usize) -> (Input, State) {
+    let input = generate(seed, h, w);
+    let mut rng = Pcg64Mcg::new(seed as u128);
+    let mut state = State::new(&input, !0, input.s);
+    extend_tail(&mut rng, &input, &mut state);
+    state.evaluateScore();
+    (input, state)
+}
+
+/// p1_idx+1..=p2_idxのタイルを踏んでいない状態のseen
+pub fn seen_without_segment(input: &Input, state: &State, p1_idx: usize, p2_idx: usize) -> Vec<bool> {
+    let mut seen = state.seen_.clone();
+    for &(x, y) in &state.steps_[p1_idx + 1..=p2_idx] {
+        seen[input.tiles[x][y]] = false;
+    }
+    seen
+}
@@ -0,0 +1,62 @@
+#![allow(non_snake_case)]
+
+// 小さい盤面で厳密解(exact::solve_exact)を求め，焼きなましがどれくらい最適解に近いかを確かめる
+// 最適との差の分布は cargo test --test exact -- --nocapture で見られる
+
+use ahc002::annealing;
+use ahc002::engine::DfsLimit;
+use ahc002::exact::{optimality_gap, solve_exact};
+use ahc002::generator::generate;
+use ahc002::scorer::compute_score;
+
+mod common;
+use common::annealing_config;
+
+// 7x7〜9x9での最適との差の平均の上限 (今は0.008くらい)
+const MAX_MEAN_GAP: f64 = 0.03;
+
+// (seed, h, w)
+fn instances(sizes: &[(usize, usize)], count: u64) -> Vec<(u64, usize, usize)> {
+    sizes.iter().flat_map(|&(h, w)| (0..count).map(move |seed| (seed, h, w))).collect()
+}
+
+#[test]
+fn exact_solution_is_valid_and_bounds_annealing() {
+    for (seed, h, w) in instances(&[(4, 4), (6, 6), (7, 7), (8, 8)], 5) {
+        let input = generate(seed, h, w);
+        let exact = solve_exact(&input, DfsLimit::time(10.0));
+        assert!(exact.optimal, "seed={} {}x{}: not solved exactly", seed, h, w);
+        assert_eq!(compute_score(&input, &exact.output), Ok(exact.score), "seed={} {}x{}", seed, h, w);
+        let (score, output) = annealing::solve(&input, &annealing_config());
+        assert_eq!(compute_score(&input, &output), Ok(score), "seed={} {}x{}", seed, h, w);
+        assert!(score <= exact.score, "seed={} {}x{}: annealing {} exceeds the optimum {}", seed, h, w, score, exact.score);
+    }
+}
+
+#[test]
+fn annealing_reaches_optimum_on_tiny_boards() {
+    for (seed, h, w) in instances(&[(3, 3), (4, 4), (5, 5), (6, 6)], 5) {
+        let input = generate(seed, h, w);
+        let exact = solve_exact(&input, DfsLimit::time(10.0));
+        let (score, _) = annealing::solve(&input, &annealing_config());
+        assert_eq!(score, exact.score, "seed={} {}x{}: annealing {} / optimum {}", seed, h, w, score, exact.score);
+    }
+}
+
+#[test]
+fn optimality_gap_distribution() {
+    let mut gaps = vec![];
+    for (seed, h, w) in instances(&[(7, 7), (8, 8), (9, 9)], 5) {
+        let input = generate(seed, h, w);
+        let exact = solve_exact(&input, DfsLimit::time(10.0));
+        let (score, _) = annealing::solve(&input, &annealing_config());
+        let gap = optimality_gap(score, exact.score);
+        eprintln!("seed={} {}x{}\tannealing {}\toptimum {}\tgap {:.4}", seed, h, w, score, exact.score, gap);
+        gaps.push(gap);
+    }
+    gaps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    let optimal = gaps.iter().filter(|&&gap| gap == 0.0).count();
+    eprintln!("cases {}\toptimal {}\tmean gap {:.4}\tmedian {:.4}\tmax {:.4}", gaps.len(), optimal, mean, gaps[gaps.len() / 2], gaps[gaps.len() - 1]);
+    assert!(mean < MAX_MEAN_GAP, "mean gap {:.4}", mean);
+}
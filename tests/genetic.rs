@@ -0,0 +1,67 @@
+#![allow(non_snake_case)]
+
+// 遺伝的アルゴリズムの交叉の性質をランダムな盤面・2つの経路で確かめる
+// できた子が盤面の外に出ず同じタイルを2回踏まない経路で，scorerと同じ得点になること
+
+use ahc002::annealing::{extend_tail, AnnealingConfig};
+use ahc002::engine::DfsLimit;
+use ahc002::genetic::crossover;
+use ahc002::scorer::compute_score;
+use ahc002::*;
+use proptest::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+mod common;
+use common::random_path;
+
+// posからランダムに行き詰まるまで歩いた経路
+fn random_path_from(seed: u64, input: &Input, pos: (usize, usize)) -> State {
+    let mut rng = Pcg64Mcg::new(seed as u128);
+    let mut state = State::new(input, !0, pos);
+    extend_tail(&mut rng, input, &mut state);
+    state.evaluateScore();
+    state
+}
+
+fn config() -> AnnealingConfig {
+    AnnealingConfig { reroute_limit: DfsLimit::nodes(500), ..Default::default() }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn crossover_makes_a_valid_path(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, other in any::<u64>()) {
+        let (input, a) = random_path(seed, h, w);
+        let b = random_path_from(other, &input, input.s);
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        let child = match crossover(&mut rng, &input, &config(), &a, &b) {
+            Some(child) => child,
+            None => return Ok(()),
+        };
+        let mut used = vec![false; input.tile_count()];
+        for &(x, y) in &child.steps_ {
+            prop_assert!(x < input.h && y < input.w, "out of the board: ({}, {})", x, y);
+            prop_assert!(!used[input.tiles[x][y]], "tile {} is visited twice", input.tiles[x][y]);
+            used[input.tiles[x][y]] = true;
+        }
+        prop_assert_eq!(child.steps_[0], input.s);
+        prop_assert_eq!(child.steps_.len(), child.output_.len() + 1);
+        prop_assert_eq!(compute_score(&input, &child.output_), Ok(child.game_score_));
+    }
+}
+
+#[test]
+fn crossover_needs_a_shared_cell() {
+    // 1行の盤面で，左端から右へ1歩と右端から左へ1歩の経路は同じマスを通らない
+    let input = Input::parse("0 0\n0 1 2 3\n1 2 3 4\n").unwrap();
+    let mut a = State::new(&input, !0, (0, 0));
+    a.advanceByOutput(&input, "R");
+    let mut b = State::new(&input, !0, (0, 3));
+    b.advanceByOutput(&input, "L");
+    let mut rng = Pcg64Mcg::new(0);
+    assert!(crossover(&mut rng, &input, &config(), &a, &b).is_none());
+    // 同じ経路どうしなら交叉できる
+    let child = crossover(&mut rng, &input, &config(), &a, &a).unwrap();
+    assert_eq!(compute_score(&input, &child.output_), Ok(child.game_score_));
+}
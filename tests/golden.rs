@@ -0,0 +1,149 @@
+#![allow(non_snake_case)]
+
+// 各解法をシード固定の盤面で決定的な予算(ノード数・反復回数)で動かし，
+// 出力をscorerで検証したうえで tests/golden_baseline.txt の得点を下回っていないか確かめる
+// 解法を改善して得点が上がったら UPDATE_GOLDEN=1 cargo test --test golden で基準を書き直す
+
+use ahc002::annealing::{self, AnnealingConfig};
+use ahc002::dfs::DfsOrder;
+use ahc002::engine::DfsLimit;
+use ahc002::generator::generate;
+use ahc002::genetic::{self, GeneticConfig};
+use ahc002::lns::{self, LnsConfig};
+use ahc002::mcts::{self, MctsConfig};
+use ahc002::region::region_initial_state;
+use ahc002::scorer::compute_score;
+use ahc002::search::*;
+use ahc002::tail::TailPolicy;
+use ahc002::*;
+use rand_pcg::Pcg64Mcg;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+mod common;
+use common::annealing_config;
+
+// (seed, h, w)
+const INSTANCES: [(u64, usize, usize); 4] = [(1, 10, 10), (2, 15, 15), (3, 20, 20), (4, 20, 30)];
+
+// 入力を受け取って出力を返す解法
+type Strategy = fn(&Input) -> String;
+
+fn strategies() -> Vec<(&'static str, Strategy)> {
+    vec![
+        ("random", |input| {
+            let mut rng = Pcg64Mcg::new(20210325);
+            play(input, State::new(input, !0, input.s), None, |state| randomAction(&mut rng, input, state)).output_
+        }),
+        ("greedy", |input| play(input, State::new(input, !0, input.s), None, |state| greedyAction(input, state)).output_),
+        ("beam", |input| play(input, State::new(input, !0, input.s), None, |state| beamSearchAction(input, state, 3, 3)).output_),
+        ("chokudai", |input| play(input, State::new(input, !0, input.s), None, |state| chokudaiSearchAction(input, state, 2, 3, 3)).output_),
+        ("chokudai_full", |input| chokudaiSearch(input, &State::new(input, !0, input.s), 2, Some(30), None).output_),
+        ("mcts", |input| {
+            let config = MctsConfig { playouts_per_action: Some(30), ..Default::default() };
+            mcts::solve(input, State::new(input, !0, input.s), &config).0.output_
+        }),
+        ("dfs", |input| {
+            let config = annealing_config();
+            let mut rng = Pcg64Mcg::new(config.seed);
+            let states = annealing::make_initial_states(&mut rng, input, &config);
+            states.peek().unwrap().output_.clone()
+        }),
+        ("dfs_warnsdorff", |input| {
+            let config = AnnealingConfig { dfs_orders: vec![DfsOrder::Warnsdorff], ..annealing_config() };
+            let mut rng = Pcg64Mcg::new(config.seed);
+            let states = annealing::make_initial_states(&mut rng, input, &config);
+            states.peek().unwrap().output_.clone()
+        }),
+        ("region", |input| region_initial_state(input, 5, DfsLimit::nodes(300)).output_),
+        ("annealing", |input| annealing::solve(input, &annealing_config()).1),
+        ("annealing_multi_initial", |input| {
+            let config = AnnealingConfig { solution_size: 4, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_diverse_initial", |input| {
+            let config = AnnealingConfig { solution_size: 4, initial_min_distance: 20, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("genetic", |input| {
+            let config = GeneticConfig { time_limit: f64::INFINITY, max_generations: Some(20), population_size: 8, offspring: 8, annealing: annealing_config(), ..Default::default() };
+            genetic::solve(input, &config).1
+        }),
+        ("lns", |input| {
+            let config = LnsConfig { time_limit: f64::INFINITY, max_iterations: Some(300), annealing: annealing_config(), ..Default::default() };
+            lns::solve(input, &config).1
+        }),
+        ("annealing_free_space_map", |input| {
+            let config = AnnealingConfig { free_space_map: true, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_both_ends", |input| {
+            let config = AnnealingConfig { prefix_rebuild_prob: 0.1, tail_regrow_prob: 0.1, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_polish", |input| {
+            let config = AnnealingConfig { polish_fraction: 0.25, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_tail_lookahead", |input| {
+            let config = AnnealingConfig { tail_policy: TailPolicy::Lookahead(3), tail_rollouts: 4, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_tail_warnsdorff", |input| {
+            let config = AnnealingConfig { tail_policy: TailPolicy::Warnsdorff, ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+        ("annealing_branch_and_bound", |input| {
+            let config = AnnealingConfig { branch_and_bound: true, reroute_limit: DfsLimit::nodes(500), ..annealing_config() };
+            annealing::solve(input, &config).1
+        }),
+    ]
+}
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden_baseline.txt")
+}
+
+// "解法 seed 得点" の行を読む
+fn read_baseline() -> BTreeMap<(String, u64), i32> {
+    let src = std::fs::read_to_string(baseline_path()).unwrap_or_default();
+    let mut baseline = BTreeMap::new();
+    for line in src.lines().filter(|line| !line.starts_with('#') && !line.trim().is_empty()) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        baseline.insert((tokens[0].to_string(), tokens[1].parse().unwrap()), tokens[2].parse().unwrap());
+    }
+    baseline
+}
+
+#[test]
+fn golden() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1");
+    let baseline = read_baseline();
+    let mut scores = BTreeMap::new();
+    let mut failures = vec![];
+    for &(seed, h, w) in &INSTANCES {
+        let input = generate(seed, h, w);
+        for (name, strategy) in strategies() {
+            let output = strategy(&input);
+            let score = match compute_score(&input, &output) {
+                Ok(score) => score,
+                Err(e) => panic!("{} seed={}: invalid output: {}", name, seed, e),
+            };
+            match baseline.get(&(name.to_string(), seed)) {
+                Some(&expected) if score < expected => failures.push(format!("{} seed={}: {} < baseline {}", name, seed, score, expected)),
+                None if !update => failures.push(format!("{} seed={}: no baseline (score {})", name, seed, score)),
+                _ => {}
+            }
+            scores.insert((name.to_string(), seed), score);
+        }
+    }
+    if update {
+        let mut src = String::from("# 解法 seed 得点 (UPDATE_GOLDEN=1 cargo test --test golden で再生成)\n");
+        for ((name, seed), score) in &scores {
+            src += &format!("{} {} {}\n", name, seed, score);
+        }
+        std::fs::write(baseline_path(), src).unwrap();
+        return;
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,87 @@
+#![allow(non_snake_case)]
+
+// 初期解の作り方(annealing::make_initial_states)を本番と同じ時間の打ち切り(first_dfs_limit)で動かして確かめる
+// golden.rsはノード数で打ち切るので，DFSの1ノードあたりが重くなっても気づけない
+// 以前の出力(initial_output)を初期解に加えるところも確かめる
+
+use ahc002::annealing::{make_initial_states, make_initial_states_until, solve, AnnealingConfig};
+use ahc002::engine::DfsLimit;
+use ahc002::generator::generate;
+use ahc002::testcase;
+use ahc002::topk::edge_distance;
+use ahc002::*;
+use rand_pcg::Pcg64Mcg;
+use std::path::PathBuf;
+
+mod common;
+
+fn load_case() -> Input {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testcases").join("0.yml");
+    testcase::load(&path).unwrap().remove(0).input
+}
+
+fn best_initial_score(input: &Input, config: &AnnealingConfig) -> (i32, Vec<Actions>) {
+    let mut rng = Pcg64Mcg::new(config.seed);
+    let states = make_initial_states(&mut rng, input, config).into_sorted_vec();
+    let best = states.last().unwrap().game_score_;
+    let actions = states.iter().map(|state| state.output_.chars().map(|c| char_to_action(c).unwrap()).collect()).collect();
+    (best, actions)
+}
+
+#[test]
+fn diverse_initial_states_keep_dfs_quality_under_time_limit() {
+    let input = load_case();
+    let plain = AnnealingConfig { solution_size: 4, ..Default::default() };
+    let diverse = AnnealingConfig { initial_min_distance: 20, ..plain.clone() };
+    let (plain_best, _) = best_initial_score(&input, &plain);
+    let (diverse_best, actions) = best_initial_score(&input, &diverse);
+    // 似ているかの判定でDFSが遅くなると，同じ時間で見つかる経路がずっと悪くなる (以前は35000に対して9000くらいだった)
+    assert!(diverse_best * 10 >= plain_best * 8, "diverse {} / plain {}", diverse_best, plain_best);
+    // 初期解はお尻を伸ばす前に互いにinitial_min_distance以上離れているので，伸ばした後も同じ経路はない
+    for (k, a) in actions.iter().enumerate() {
+        for b in &actions[k + 1..] {
+            assert!(edge_distance(a, b) > 0, "duplicated initial states");
+        }
+    }
+}
+
+#[test]
+fn initial_dfs_stops_at_deadline() {
+    let input = load_case();
+    // 8通りの順番でDFSを0.004秒ずつすると0.032秒かかるところを，0.01秒で打ち切る
+    let config = AnnealingConfig::default();
+    let mut rng = Pcg64Mcg::new(config.seed);
+    let start = get_time();
+    let states = make_initial_states_until(&mut rng, &input, &config, start + 0.01);
+    let elapsed = get_time() - start;
+    assert!(!states.is_empty());
+    assert!(elapsed < 0.02, "took {:.4}s", elapsed);
+}
+
+#[test]
+fn valid_initial_output_is_used() {
+    let input = generate(1, 12, 12);
+    // 焼きなました経路を，ほとんどDFSをしない設定の初期解に加える
+    let (warm_score, warm_output) = solve(&input, &common::annealing_config());
+    let config = AnnealingConfig { first_dfs_limit: DfsLimit::nodes(10), ..common::annealing_config() };
+    let (cold_best, _) = best_initial_score(&input, &config);
+    assert!(cold_best < warm_score, "{} >= {}", cold_best, warm_score);
+    let warm = AnnealingConfig { initial_output: Some(warm_output.clone()), ..config };
+    let mut rng = Pcg64Mcg::new(warm.seed);
+    let best = make_initial_states(&mut rng, &input, &warm).into_sorted_vec().pop().unwrap();
+    // お尻が伸びることはあっても，前回の経路から始まる
+    assert!(best.output_.starts_with(&warm_output), "{}", best.output_);
+    assert!(best.game_score_ >= warm_score);
+}
+
+#[test]
+fn invalid_initial_output_is_ignored() {
+    let input = generate(2, 12, 12);
+    let config = common::annealing_config();
+    let (_, plain) = best_initial_score(&input, &config);
+    for output in ["LX", "LR"] {
+        let invalid = AnnealingConfig { initial_output: Some(output.to_string()), ..config.clone() };
+        let (_, actions) = best_initial_score(&input, &invalid);
+        assert_eq!(actions, plain, "{}", output);
+    }
+}
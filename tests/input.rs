@@ -0,0 +1,78 @@
+#![allow(non_snake_case)]
+
+// 入力の検証(Input::parse)が，おかしな入力に対して正しい種類と位置(行・列)のエラーを返すか
+
+use ahc002::input::{ParseError, ParseErrorKind};
+use ahc002::*;
+
+// 2x2の正しい入力
+// tiles: [[0, 0], [1, 2]], ps: [[1, 2], [3, 4]]
+const VALID: &str = "0 0\n0 0\n1 2\n1 2\n3 4\n";
+
+#[test]
+fn valid_input_is_accepted() {
+    let input = Input::parse(VALID).unwrap();
+    assert_eq!((input.h, input.w, input.s), (2, 2, (0, 0)));
+    assert_eq!(input.tiles, vec![vec![0, 0], vec![1, 2]]);
+    assert_eq!(input.ps, vec![vec![1, 2], vec![3, 4]]);
+    assert_eq!(input.tile_count(), 3);
+}
+
+#[test]
+fn errors_report_kind_line_and_column() {
+    use ParseErrorKind::*;
+    // (説明, 入力, 種類, 行, 列)
+    let cases: Vec<(&str, &str, ParseErrorKind, usize, usize)> = vec![
+        // 途中で切れている
+        ("empty", "", MissingLine, 1, 1),
+        ("only s", "0 0\n", MissingLine, 2, 1),
+        ("missing ps row", "0 0\n0 0\n1 2\n1 2\n", ExtraLine, 4, 1),
+        ("short row", "0 0\n0 0\n1 2\n1 2\n3\n", WrongTokenCount { expected: 2, found: 1 }, 5, 2),
+        ("short s", "0\n0 0\n1 2\n1 2\n3 4\n", WrongTokenCount { expected: 2, found: 1 }, 1, 2),
+        ("long row", "0 0\n0 0\n1 2\n1 2\n3 4 5\n", WrongTokenCount { expected: 2, found: 3 }, 5, 5),
+        // 数値として読めない
+        ("letter in tiles", "0 0\n0 0\n1 x\n1 2\n3 4\n", InvalidNumber("x".to_string()), 3, 3),
+        ("negative s", "0 -1\n0 0\n1 2\n1 2\n3 4\n", InvalidNumber("-1".to_string()), 1, 3),
+        ("float point", "0 0\n0 0\n1 2\n1.5 2\n3 4\n", InvalidNumber("1.5".to_string()), 4, 1),
+        ("blank lines and indent count", "0 0\n\n0 0\n  1 x\n1 2\n3 4\n", InvalidNumber("x".to_string()), 4, 5),
+        // sが盤面の外
+        ("s row out of range", "2 0\n0 0\n1 2\n1 2\n3 4\n", StartOutOfBounds, 1, 1),
+        ("s column out of range", "0  5\n0 0\n1 2\n1 2\n3 4\n", StartOutOfBounds, 1, 4),
+        // 得点が範囲外
+        ("point too large", "0 0\n0 0\n1 2\n1 2\n3 100\n", PointOutOfRange(100), 5, 3),
+        ("negative point", "0 0\n0 0\n1 2\n-1 2\n3 4\n", PointOutOfRange(-1), 4, 1),
+        // タイル番号がおかしい
+        ("tile id too large", "0 0\n0 0\n1 9\n1 2\n3 4\n", TileIdOutOfRange(9), 3, 3),
+        ("tile id skipped", "0 0\n0 0\n2 3\n1 2\n3 4\n", MissingTileId(1), 3, 1),
+        ("tile with three cells", "0 0\n0 0\n0 1\n1 2\n3 4\n", TileTooLarge(0), 3, 1),
+        ("tile cells apart", "0 0\n0 1\n2 0\n1 2\n3 4\n", TileNotAdjacent(0), 3, 3),
+        // 問題が複数あれば入力の中で一番前のもの
+        ("s before points", "0 2\n0 0\n1 2\n1 2\n3 100\n", StartOutOfBounds, 1, 3),
+        ("tiles before points", "0 0\n0 0\n1 9\n1 200\n3 4\n", TileIdOutOfRange(9), 3, 3),
+    ];
+    for (name, src, kind, line, column) in cases {
+        assert_eq!(Input::parse(src).err(), Some(ParseError { line, column, kind }), "{}", name);
+    }
+}
+
+#[test]
+fn error_message_has_position() {
+    let e = Input::parse("0 0\n0 0\n1 x\n1 2\n3 4\n").unwrap_err();
+    assert_eq!(e.to_string(), "line 3, column 3: invalid number `x`");
+}
+
+#[test]
+fn section_lengths_are_inferred_from_the_line_count() {
+    // 入力にhはないので，tilesとpsは残りの行を半分ずつに分けたものとして読む
+    // 行数が奇数なら最後の行が余る
+    let e = Input::parse("0 0\n0 0\n1 2\n1 2\n3 4\n5 6\n").unwrap_err();
+    assert_eq!((e.kind, e.line), (ParseErrorKind::ExtraLine, 6));
+    // 偶数なら，tiles 3行・ps 1行の3x2の盤面でも2x2の盤面として読まれる
+    // (3行目のtilesが2行目と縦に繋がったタイルだけで，得点としても範囲内のとき．区別する手がかりが入力にない)
+    let input = Input::parse("0 0\n0 0\n1 2\n1 2\n5 6\n").unwrap();
+    assert_eq!((input.h, input.w), (2, 2));
+    assert_eq!(input.ps, vec![vec![1, 2], vec![5, 6]]);
+    // psの行がtilesの側に入ると，たいていタイル番号の検証に引っかかる (tiles 1行・ps 3行)
+    let e = Input::parse("0 0\n0 1\n5 6\n7 8\n9 10\n").unwrap_err();
+    assert_eq!((e.kind, e.line), (ParseErrorKind::TileIdOutOfRange(5), 3));
+}
@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+
+// LNSの壊して直す処理の性質をランダムな盤面・経路・区間で確かめる
+// ビームサーチで直した区間が，長方形の中で踏んでいないタイルだけを通ってgoalにちょうど着き，得点が合うこと
+// 壊して直した経路が正しく，scorerと同じ得点で，もとより悪くならないこと
+
+use ahc002::annealing::splice;
+use ahc002::lns::{beam_repair, destroy_and_repair, LnsConfig};
+use ahc002::scorer::compute_score;
+use ahc002::*;
+use proptest::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+mod common;
+use common::{random_path, seen_without_segment};
+
+// stepsのマスを囲む長方形 (imin, imax, jmin, jmax)
+fn bounding_box(steps: &[(usize, usize)]) -> (usize, usize, usize, usize) {
+    let imin = steps.iter().map(|p| p.0).min().unwrap();
+    let imax = steps.iter().map(|p| p.0).max().unwrap();
+    let jmin = steps.iter().map(|p| p.1).min().unwrap();
+    let jmax = steps.iter().map(|p| p.1).max().unwrap();
+    (imin, imax, jmin, jmax)
+}
+
+// p1からactionsで進んだマスの列 (p1は含まない)
+fn walk(p1: (usize, usize), actions: &[Action]) -> Vec<(usize, usize)> {
+    let mut pos = p1;
+    actions
+        .iter()
+        .map(|&action| {
+            pos = (pos.0.wrapping_add(DIJ[action].0), pos.1.wrapping_add(DIJ[action].1));
+            pos
+        })
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn beam_repair_reaches_the_goal(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, a in any::<usize>(), b in any::<usize>(), to_end in any::<bool>(), beam_width in 1usize..8) {
+        let (input, state) = random_path(seed, h, w);
+        let len = state.steps_.len();
+        prop_assume!(len >= 2);
+        // to_endなら区間をお尻まで取り，終点を自由にする
+        let (p1_idx, p2_idx) = if to_end {
+            (a % (len - 1), len - 1)
+        } else {
+            let (x, y) = (a % len, b % len);
+            prop_assume!(x != y);
+            (x.min(y), x.max(y))
+        };
+        let seen = seen_without_segment(&input, &state, p1_idx, p2_idx);
+        let bounds = bounding_box(&state.steps_[p1_idx..=p2_idx]);
+        let p1 = state.steps_[p1_idx];
+        let goal = if to_end { None } else { Some(state.steps_[p2_idx]) };
+        let (score, actions) = match beam_repair(&input, &seen, p1, goal, bounds, beam_width) {
+            Some(repaired) => repaired,
+            None => return Ok(()),
+        };
+        // 長方形の中で，踏んでいないタイルだけを1回ずつ踏む
+        let cells = walk(p1, &actions);
+        let mut used = seen.clone();
+        for &(x, y) in &cells {
+            prop_assert!(bounds.0 <= x && x <= bounds.1 && bounds.2 <= y && y <= bounds.3, "out of the bounds: ({}, {})", x, y);
+            prop_assert!(!used[input.tiles[x][y]], "tile {} is visited twice", input.tiles[x][y]);
+            used[input.tiles[x][y]] = true;
+        }
+        prop_assert_eq!(score, cells.iter().map(|&(x, y)| input.ps[x][y]).sum::<i32>());
+        match goal {
+            // goalにちょうど着くので，もとの経路の後半にそのまま繋がる
+            Some(goal) => {
+                prop_assert_eq!(cells.last(), Some(&goal));
+                let next = splice(&input, &state.output_, p1_idx, &actions, p2_idx);
+                prop_assert_eq!(&next.steps_[p1_idx + actions.len()..], &state.steps_[p2_idx..]);
+                prop_assert_eq!(compute_score(&input, &next.output_), Ok(next.game_score_));
+            }
+            // 終点が自由なときは，p1から少なくとも1手は進む
+            None => prop_assert!(!actions.is_empty()),
+        }
+    }
+
+    #[test]
+    fn destroy_and_repair_keeps_a_valid_path(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, steps in 1usize..20) {
+        let (input, mut state) = random_path(seed, h, w);
+        let config = LnsConfig { rect_min: 1, rect_max: 6, beam_width: 4, ..Default::default() };
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        for _ in 0..steps {
+            let before = state.game_score_;
+            let accepted = destroy_and_repair(&mut rng, &input, &config, &mut state);
+            prop_assert_eq!(compute_score(&input, &state.output_), Ok(state.game_score_));
+            prop_assert_eq!(state.steps_.len(), state.output_.len() + 1);
+            prop_assert_eq!(state.steps_[0], input.s);
+            if accepted {
+                prop_assert!(state.game_score_ >= before, "{} < {}", state.game_score_, before);
+            } else {
+                prop_assert_eq!(state.game_score_, before);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+#![allow(non_snake_case)]
+
+// 長時間焼くモード(offline::run)の途中経過の書き出し・読み込みと，止める条件を確かめる
+// チェックポイントはテストごとに一時ディレクトリの下に書く
+
+use ahc002::annealing::AnnealingConfig;
+use ahc002::engine::DfsLimit;
+use ahc002::generator::generate;
+use ahc002::offline::{run, Checkpoint, CheckpointError, OfflineConfig};
+use ahc002::scorer::compute_score;
+use std::path::Path;
+
+mod common;
+use common::temp_dir;
+
+fn offline_config(checkpoint_path: &Path) -> OfflineConfig {
+    OfflineConfig {
+        annealing: AnnealingConfig {
+            first_dfs_limit: DfsLimit::nodes(2000),
+            reroute_limit: DfsLimit::nodes(200),
+            ..Default::default()
+        },
+        round_iterations: 500,
+        checkpoint_path: checkpoint_path.to_path_buf(),
+        checkpoint_interval: 0.05,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn checkpoint_round_trip() {
+    let dir = temp_dir("offline-round-trip");
+    let checkpoint = Checkpoint { seed: u128::MAX - 1, round: 3, iter: 42, best_score: 12345, best: "RRDL".to_string(), current: "RD".to_string() };
+    let path = dir.join("checkpoint.txt");
+    assert_eq!(Checkpoint::from_text(&path, &checkpoint.to_text()).unwrap(), checkpoint);
+    checkpoint.save(&path).unwrap();
+    assert!(!path.with_extension("tmp").exists());
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+    // 空の経路(sから動かない)も読み戻せる
+    let empty = Checkpoint { best: String::new(), current: String::new(), ..checkpoint };
+    assert_eq!(Checkpoint::from_text(&path, &empty.to_text()).unwrap(), empty);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn checkpoint_format_errors_report_the_line() {
+    let path = Path::new("checkpoint.txt");
+    let src = "seed 1\nround x\niter 0\nbest_score 0\nbest \ncurrent \n";
+    match Checkpoint::from_text(path, src) {
+        Err(CheckpointError::Format(_, line, msg)) => assert_eq!((line, msg.as_str()), (2, "invalid round `x`")),
+        other => panic!("{:?}", other),
+    }
+    // キーが足りなければ最後の行の次を指す
+    let src = "seed 1\nround 0\niter 0\nbest_score 0\nbest \n";
+    match Checkpoint::from_text(path, src) {
+        Err(CheckpointError::Format(_, line, msg)) => assert_eq!((line, msg.as_str()), (6, "missing `current`")),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn run_resumes_from_checkpoint() {
+    let dir = temp_dir("offline-resume");
+    let input = generate(1, 12, 12);
+    let path = dir.join("checkpoint.txt");
+    let config = OfflineConfig { wall_limit: Some(0.1), ..offline_config(&path) };
+
+    // 1回目: wall_limitで止まり，途中経過が残る
+    let (first_score, first_output) = run(&input, &config).unwrap();
+    assert_eq!(compute_score(&input, &first_output), Ok(first_score));
+    let first = Checkpoint::load(&path).unwrap();
+    assert_eq!((first.best_score, first.best.as_str()), (first_score, first_output.as_str()));
+    assert!(compute_score(&input, &first.current).is_ok());
+
+    // 2回目: 続きから焼くので，周回は進み，最良解は悪くならない
+    let (second_score, second_output) = run(&input, &config).unwrap();
+    assert_eq!(compute_score(&input, &second_output), Ok(second_score));
+    assert!(second_score >= first_score, "{} < {}", second_score, first_score);
+    let second = Checkpoint::load(&path).unwrap();
+    assert!((second.round, second.iter) > (first.round, first.iter), "{:?} -> {:?}", (first.round, first.iter), (second.round, second.iter));
+    assert_ne!(second.seed, first.seed);
+
+    // 別の盤面のチェックポイントからは再開しない
+    let other = generate(2, 12, 12);
+    match run(&other, &config) {
+        Err(CheckpointError::Replay(..)) => {}
+        other => panic!("{:?}", other.map(|(score, _)| score)),
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn run_stops_at_target_score() {
+    let dir = temp_dir("offline-target");
+    let input = generate(3, 12, 12);
+    let path = dir.join("checkpoint.txt");
+    // 少しだけ焼いて届く得点を知っておき，その得点を目標にする
+    let (score, _) = run(&input, &OfflineConfig { wall_limit: Some(0.05), ..offline_config(&path) }).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    // 届かなかったときにテストが止まらなくならないようにwall_limitも付けておく (届けばそちらでは止まらない)
+    let config = OfflineConfig { target_score: Some(score), wall_limit: Some(20.0), ..offline_config(&path) };
+    let start = std::time::Instant::now();
+    let (reached, output) = run(&input, &config).unwrap();
+    assert!(start.elapsed().as_secs_f64() < 20.0, "stopped by the wall limit");
+    assert!(reached >= score, "{} < {}", reached, score);
+    assert_eq!(compute_score(&input, &output), Ok(reached));
+    assert_eq!(Checkpoint::load(&path).unwrap().best_score, reached);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn run_stops_at_wall_limit() {
+    let dir = temp_dir("offline-wall");
+    let input = generate(4, 12, 12);
+    let path = dir.join("checkpoint.txt");
+    let config = OfflineConfig { wall_limit: Some(0.2), ..offline_config(&path) };
+    let start = std::time::Instant::now();
+    run(&input, &config).unwrap();
+    let elapsed = start.elapsed().as_secs_f64();
+    assert!((0.2..1.0).contains(&elapsed), "{:.3}s", elapsed);
+    assert!(path.exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
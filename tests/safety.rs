@@ -0,0 +1,95 @@
+#![allow(non_snake_case)]
+
+// 解法がpanicしても，正しくない経路を返しても，SafetyNetに渡された最良の正しい経路が残ること
+// 見張りのスレッドとmainが同時に出力しようとしても，ちょうど1回だけ全部出力されること
+
+use ahc002::annealing::{self, AnnealingConfig};
+use ahc002::engine::DfsLimit;
+use ahc002::generator::generate;
+use ahc002::safety::{guard, SafetyNet};
+use ahc002::scorer::compute_score;
+use std::io::Write;
+use std::sync::{Arc, Barrier, Mutex};
+use std::time::Duration;
+
+#[test]
+fn guard_falls_back_on_panic() {
+    let input = generate(2, 10, 10);
+    let net = SafetyNet::new(&input);
+    let config = AnnealingConfig {
+        time_limit: f64::INFINITY,
+        max_iterations: Some(200),
+        first_dfs_limit: DfsLimit::nodes(2000),
+        reroute_limit: DfsLimit::nodes(200),
+        safety_net: Some(net.clone()),
+        ..Default::default()
+    };
+    let (expected, _) = annealing::solve(&input, &config);
+    // 焼きなましが途中で渡した最良解は，返り値と同じ得点
+    assert_eq!(net.best().0, expected);
+    let (score, output) = guard(&net, || panic!("solver failure"));
+    assert_eq!(score, expected);
+    assert_eq!(compute_score(&input, &output), Ok(score));
+}
+
+#[test]
+fn invalid_outputs_are_ignored() {
+    let input = generate(4, 6, 6);
+    let net = SafetyNet::new(&input);
+    let start_score = input.ps[input.s.0][input.s.1];
+    assert_eq!(net.best(), (start_score, String::new()));
+    // 盤面の外に出る・同じタイルを踏む・LRUD以外の文字
+    for output in ["LLLLLLL", "LR", "RL", "X"] {
+        assert!(!net.offer(output), "{}", output);
+    }
+    let (score, output) = guard(&net, || (i32::MAX, "LLLLLLL".to_string()));
+    assert_eq!((score, output), (start_score, String::new()));
+}
+
+// 1バイトずつ間をあけて書き込む出力先 (書いている途中にもう一方のスレッドが割り込めるように)
+#[derive(Clone)]
+struct SlowWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SlowWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &byte in buf {
+            std::thread::sleep(Duration::from_micros(200));
+            self.0.lock().unwrap().push(byte);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn emit_race_at_deadline_prints_once_and_in_full() {
+    let input = generate(2, 10, 10);
+    let output = annealing::solve(&input, &AnnealingConfig { time_limit: f64::INFINITY, max_iterations: Some(200), first_dfs_limit: DfsLimit::nodes(2000), reroute_limit: DfsLimit::nodes(200), ..Default::default() }).1;
+    let expected = format!("{}\n", output.trim());
+    for _ in 0..20 {
+        let net = SafetyNet::new(&input);
+        assert!(net.offer(&output));
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let barrier = Arc::new(Barrier::new(2));
+        // 見張りのスレッドとmainが締め切りちょうどに同時に出力しようとする
+        // どちらが勝っても，emit_toから戻った時点で(main側はそのままプロセスを終えるので)全部書き終わっていないといけない
+        let handles: Vec<_> = ["watchdog", "main"]
+            .iter()
+            .map(|&name| {
+                let (net, buffer, barrier) = (net.clone(), buffer.clone(), barrier.clone());
+                let expected = expected.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let emitted = net.emit_to(&mut SlowWriter(buffer.clone()));
+                    assert_eq!(String::from_utf8(buffer.lock().unwrap().clone()).unwrap(), expected, "{} returned before the output was complete", name);
+                    emitted
+                })
+            })
+            .collect();
+        let emitted: Vec<bool> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        assert_eq!(emitted.iter().filter(|&&e| e).count(), 1, "{:?}", emitted);
+    }
+}
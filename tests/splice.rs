@@ -0,0 +1,148 @@
+#![allow(non_snake_case)]
+
+// 焼きなましの繋ぎ変え(splice)の性質をランダムな盤面・経路・p1/p2で確かめる
+// 見つかった繋ぎ方をどれを採用しても，できた経路が
+// 盤面の外に出ない・同じタイルを2回踏まない・p1とp2をちょうど通る・scorerと同じ得点になる こと
+// sからの最初の数手の作り直し・お尻の伸ばし直しでも，経路が正しく得点の差分が合うこと
+// 繋ぎ変えながら差分更新した空き具合(FreeSpaceMap)が，作り直したものと一致すること
+
+use ahc002::annealing::{anneal_step_with, choose_points, choose_points_with, rebuild_prefix, regrow_tail, splice, AnnealingConfig};
+use ahc002::dfs::dfs_to_destination;
+use ahc002::engine::{DfsLimit, DfsStatus, PathState};
+use ahc002::freespace::FreeSpaceMap;
+use ahc002::scorer::compute_score;
+use ahc002::segment::optimize_segment;
+use ahc002::topk::TopK;
+use ahc002::*;
+use proptest::prelude::*;
+use rand_pcg::Pcg64Mcg;
+
+mod common;
+use common::{random_path, seen_without_segment};
+
+// 繋ぎ変えた経路が満たすべき性質
+fn check_spliced(input: &Input, old: &State, p1_idx: usize, actions: &[Action], p2_idx: usize) -> Result<(), TestCaseError> {
+    let next = splice(input, &old.output_, p1_idx, actions, p2_idx);
+    // 盤面の中で，同じタイルを2回踏んでいない
+    let mut used = vec![false; input.tile_count()];
+    for &(x, y) in &next.steps_ {
+        prop_assert!(x < input.h && y < input.w, "out of the board: ({}, {})", x, y);
+        prop_assert!(!used[input.tiles[x][y]], "tile {} is visited twice", input.tiles[x][y]);
+        used[input.tiles[x][y]] = true;
+    }
+    // 前半はそのまま，新しい区間はp2にちょうど着き，後半はもとの経路と同じマスを通る
+    prop_assert_eq!(&next.steps_[..=p1_idx], &old.steps_[..=p1_idx]);
+    prop_assert_eq!(next.steps_[p1_idx + actions.len()], old.steps_[p2_idx]);
+    prop_assert_eq!(&next.steps_[p1_idx + actions.len()..], &old.steps_[p2_idx..]);
+    prop_assert_eq!(next.steps_.len(), next.output_.len() + 1);
+    // 得点はscorerの計算と一致する
+    prop_assert_eq!(compute_score(input, &next.output_), Ok(next.game_score_));
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn splice_with_dfs_to_destination(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, a in any::<usize>(), b in any::<usize>()) {
+        let (input, state) = random_path(seed, h, w);
+        let len = state.steps_.len();
+        prop_assume!(len >= 2);
+        let (p1_idx, p2_idx) = {
+            let (x, y) = (a % len, b % len);
+            prop_assume!(x != y);
+            (x.min(y), x.max(y))
+        };
+        let p1 = state.steps_[p1_idx];
+        let p2 = state.steps_[p2_idx];
+        let seen = seen_without_segment(&input, &state, p1_idx, p2_idx);
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        let mut action_top = TopK::new(4);
+        let start = PathState::new(&input, p1, seen, 0);
+        let status = dfs_to_destination(&mut rng, &input, &DIR_LIST, start, p2, &mut action_top, DfsLimit::nodes(2000));
+        // もとの区間は必ず候補になりうるので，探索しきったなら少なくとも1つは見つかる
+        if status == DfsStatus::Finished {
+            prop_assert!(!action_top.is_empty());
+        }
+        for (_, actions) in action_top.into_sorted_vec() {
+            check_spliced(&input, &state, p1_idx, &actions, p2_idx)?;
+        }
+    }
+
+    #[test]
+    fn splice_with_optimize_segment(seed in any::<u64>(), h in 2usize..8, w in 2usize..8, a in any::<usize>(), b in any::<usize>()) {
+        let (input, state) = random_path(seed, h, w);
+        let len = state.steps_.len();
+        prop_assume!(len >= 2);
+        let (p1_idx, p2_idx) = {
+            let (x, y) = (a % len, b % len);
+            prop_assume!(x != y);
+            (x.min(y), x.max(y))
+        };
+        let p1 = state.steps_[p1_idx];
+        let p2 = state.steps_[p2_idx];
+        let mut seen = seen_without_segment(&input, &state, p1_idx, p2_idx);
+        let seen_before = seen.clone();
+        let path = optimize_segment(&input, &mut seen, p1, p2, DfsLimit::nodes(20000));
+        prop_assert_eq!(&seen, &seen_before);
+        // もとの区間があるので経路は必ず見つかり，探索しきったならもとの区間以上の得点になる
+        let path = path.expect("the original segment always connects p1 and p2");
+        let old_segment_score: i32 = state.steps_[p1_idx + 1..=p2_idx].iter().map(|&(x, y)| input.ps[x][y]).sum();
+        if path.optimal {
+            prop_assert!(path.score >= old_segment_score);
+        }
+        check_spliced(&input, &state, p1_idx, &path.actions, p2_idx)?;
+    }
+
+    #[test]
+    fn choose_points_returns_valid_indices(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, t in 0.0f64..1.0) {
+        let (input, state) = random_path(seed, h, w);
+        prop_assume!(state.steps_.len() >= 2);
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        let (p1_idx, p2_idx) = choose_points(&mut rng, &input, &state, t);
+        prop_assert!(p1_idx < p2_idx && p2_idx < state.steps_.len(), "p1_idx = {}, p2_idx = {}, len = {}", p1_idx, p2_idx, state.steps_.len());
+    }
+
+    #[test]
+    fn free_space_map_follows_splices(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, steps in 1usize..30) {
+        let (input, mut state) = random_path(seed, h, w);
+        prop_assume!(state.steps_.len() >= 2);
+        let config = AnnealingConfig { time_limit: f64::INFINITY, reroute_limit: DfsLimit::nodes(500), ..Default::default() };
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        let mut free_space = FreeSpaceMap::new(&input, &state);
+        let (mut best_score, mut best_output) = (0, String::new());
+        for step in 0..steps {
+            let t = step as f64 / steps as f64;
+            if !anneal_step_with(&mut rng, &input, &config, &mut state, t, &mut best_score, &mut best_output, Some(&mut free_space)) {
+                break;
+            }
+            let rebuilt = FreeSpaceMap::new(&input, &state);
+            for idx in 0..state.steps_.len() {
+                prop_assert_eq!(free_space.free_at(idx), rebuilt.free_at(idx), "index {} after {} steps", idx, step + 1);
+            }
+            prop_assert_eq!(free_space.top(state.steps_.len() + 1), rebuilt.top(state.steps_.len() + 1));
+            let (p1_idx, p2_idx) = choose_points_with(&mut rng, &input, &state, t, Some(&free_space));
+            prop_assert!(p1_idx < p2_idx && p2_idx < state.steps_.len(), "p1_idx = {}, p2_idx = {}, len = {}", p1_idx, p2_idx, state.steps_.len());
+        }
+    }
+
+    #[test]
+    fn rebuild_prefix_and_regrow_tail(seed in any::<u64>(), h in 2usize..12, w in 2usize..12, a in any::<usize>(), b in any::<usize>()) {
+        let (input, state) = random_path(seed, h, w);
+        let len = state.steps_.len();
+        prop_assume!(len >= 2);
+        let config = AnnealingConfig { reroute_limit: DfsLimit::nodes(2000), ..Default::default() };
+        let mut rng = Pcg64Mcg::new(seed as u128);
+        let n = 1 + a % (len - 1);
+        if let Some((delta, actions)) = rebuild_prefix(&mut rng, &input, &config, &state, n) {
+            check_spliced(&input, &state, 0, &actions, n)?;
+            let next = splice(&input, &state.output_, 0, &actions, n);
+            prop_assert_eq!(next.game_score_ - state.game_score_, delta);
+        }
+        let cut_idx = b % len;
+        let next = regrow_tail(&mut rng, &input, &config, &state, cut_idx);
+        prop_assert_eq!(&next.steps_[..=cut_idx], &state.steps_[..=cut_idx]);
+        prop_assert!(next.legalActions(&input).is_empty());
+        prop_assert_eq!(compute_score(&input, &next.output_), Ok(next.game_score_));
+    }
+}
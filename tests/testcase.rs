@@ -0,0 +1,43 @@
+#![allow(non_snake_case)]
+
+// テストケースのYAMLをファイルから読むところ (ファイル名の付いたエラーとextendのglob) を確かめる
+// YAMLの中身の読み方はsrc/testcase.rsのテストで見る
+
+use ahc002::testcase::{load_yaml, LoadError};
+
+mod common;
+use common::temp_dir;
+
+#[test]
+fn yaml_errors_report_the_file() {
+    // load_yamlはファイル名と行番号を付けて返す
+    let dir = temp_dir("testcase-errors");
+    let path = dir.join("broken.yml");
+    std::fs::write(&path, "cases:\n  - out: LR\n").unwrap();
+    match load_yaml(&path) {
+        Err(LoadError::Yaml(p, line, msg)) => assert_eq!((p, line, msg.as_str()), (path.clone(), 2, "case without `in`")),
+        other => panic!("{:?}", other.map(|cases| cases.len())),
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn extend_expands_globs() {
+    let dir = temp_dir("testcase-extend");
+    let input = "0 0\n0 0\n1 2\n1 2\n3 4\n";
+    std::fs::create_dir_all(dir.join("data/in")).unwrap();
+    std::fs::create_dir_all(dir.join("data/out")).unwrap();
+    for name in ["b.txt", "a.txt", "note.md"] {
+        std::fs::write(dir.join("data/in").join(name), input).unwrap();
+    }
+    std::fs::write(dir.join("data/out/a.txt"), "R\n").unwrap();
+    let path = dir.join("cases.yml");
+    std::fs::write(&path, "extend:\n  - type: Text\n    path: \"./data\"\n    in: /in/*.txt\n    out: /out/*.txt\n").unwrap();
+    let cases = load_yaml(&path).unwrap();
+    // *.txtだけを名前順に，出力があればそれも読む
+    let names: Vec<String> = cases.iter().map(|case| case.name.clone()).collect();
+    assert_eq!(names, [dir.join("data/in/a.txt"), dir.join("data/in/b.txt")].map(|p| p.display().to_string()));
+    assert_eq!(cases[0].expected_output.as_deref(), Some("R\n"));
+    assert_eq!(cases[1].expected_output, None);
+    std::fs::remove_dir_all(&dir).ok();
+}